@@ -0,0 +1,298 @@
+//! Multi-Tag Items
+//!
+//! Items that carry several shared tags, plus a catalog that indexes
+//! them by tag name and answers boolean tag queries.
+
+use std::{collections::HashMap, rc::Rc};
+
+use crate::Tag;
+
+/// An item that holds shared references to any number of Tags.
+#[derive(Debug)]
+pub struct TaggedItem {
+    pub name: String,
+    pub tags: Vec<Rc<Tag>>,
+}
+
+impl TaggedItem {
+    pub fn new(name: &str, tags: Vec<Rc<Tag>>) -> Self {
+        TaggedItem {
+            name: name.to_string(),
+            tags,
+        }
+    }
+
+    pub fn has_tag(&self, name: &str) -> bool {
+        self.tags.iter().any(|tag| tag.name == name)
+    }
+}
+
+/// A boolean tag query. Empty lists impose no constraint.
+#[derive(Debug, Default, Clone)]
+pub struct TagQuery {
+    pub all_of: Vec<String>,
+    pub any_of: Vec<String>,
+    pub none_of: Vec<String>,
+}
+
+impl TagQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Item must carry every one of these tags.
+    pub fn all_of(mut self, names: &[&str]) -> Self {
+        self.all_of
+            .extend(names.iter().map(|name| name.to_string()));
+        self
+    }
+
+    /// Item must carry at least one of these tags.
+    pub fn any_of(mut self, names: &[&str]) -> Self {
+        self.any_of
+            .extend(names.iter().map(|name| name.to_string()));
+        self
+    }
+
+    /// Item must carry none of these tags.
+    pub fn none_of(mut self, names: &[&str]) -> Self {
+        self.none_of
+            .extend(names.iter().map(|name| name.to_string()));
+        self
+    }
+
+    pub fn matches(&self, item: &TaggedItem) -> bool {
+        self.all_of.iter().all(|name| item.has_tag(name))
+            && (self.any_of.is_empty() || self.any_of.iter().any(|name| item.has_tag(name)))
+            && !self.none_of.iter().any(|name| item.has_tag(name))
+    }
+}
+
+/// Owns a set of items and indexes them by tag name.
+///
+/// Tags are told apart by name alone, so every tag should come from the
+/// same `TagRegistry`. Two distinct `Rc<Tag>`s with one name would share an
+/// index entry, and `tag_usage` would count only one of them; debug builds
+/// panic on insert instead.
+#[derive(Debug, Default)]
+pub struct ItemCatalog {
+    items: Vec<TaggedItem>,
+    index: HashMap<String, Vec<usize>>,
+}
+
+impl ItemCatalog {
+    pub fn new() -> Self {
+        ItemCatalog {
+            items: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, item: TaggedItem) {
+        let idx = self.items.len();
+
+        for tag in &item.tags {
+            let postings = self.index.entry(tag.name.clone()).or_default();
+            if let Some(&first) = postings.first()
+                && first != idx
+            {
+                debug_assert!(
+                    self.items[first]
+                        .tags
+                        .iter()
+                        .any(|other| Rc::ptr_eq(other, tag)),
+                    "tag {:?} was not interned by the catalog's registry",
+                    tag.name
+                );
+            }
+            // An item listing the same tag twice is indexed once
+            if postings.last() != Some(&idx) {
+                postings.push(idx);
+            }
+        }
+
+        self.items.push(item);
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Items carrying `name`, in insertion order.
+    pub fn with_tag(&self, name: &str) -> Vec<&TaggedItem> {
+        self.postings(name)
+            .iter()
+            .map(|&idx| &self.items[idx])
+            .collect()
+    }
+
+    /// Items matching `query`, in insertion order.
+    /// Items are returned by reference, so no `Rc<Tag>` is cloned.
+    pub fn query(&self, query: &TagQuery) -> Vec<&TaggedItem> {
+        let candidates: Vec<usize> = if !query.all_of.is_empty() {
+            // Start from the rarest required tag
+            query
+                .all_of
+                .iter()
+                .map(|name| self.postings(name))
+                .min_by_key(|postings| postings.len())
+                .unwrap_or_default()
+                .to_vec()
+        } else if !query.any_of.is_empty() {
+            let mut union: Vec<usize> = query
+                .any_of
+                .iter()
+                .flat_map(|name| self.postings(name).iter().copied())
+                .collect();
+            union.sort_unstable();
+            union.dedup();
+            union
+        } else {
+            (0..self.items.len()).collect()
+        };
+
+        candidates
+            .into_iter()
+            .map(|idx| &self.items[idx])
+            .filter(|item| query.matches(item))
+            .collect()
+    }
+
+    /// Per-tag `Rc::strong_count`, sorted by tag name.
+    ///
+    /// The count covers every owner of the tag, including holders
+    /// outside this catalog.
+    pub fn tag_usage(&self) -> Vec<(String, usize)> {
+        let mut usage: Vec<(String, usize)> = self
+            .index
+            .iter()
+            .filter_map(|(name, postings)| {
+                let item = &self.items[*postings.first()?];
+                let tag = item.tags.iter().find(|tag| &tag.name == name)?;
+                Some((name.clone(), Rc::strong_count(tag)))
+            })
+            .collect();
+        usage.sort();
+        usage
+    }
+
+    fn postings(&self, name: &str) -> &[usize] {
+        self.index.get(name).map(Vec::as_slice).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::TagRegistry;
+
+    fn names(items: &[&TaggedItem]) -> Vec<String> {
+        items.iter().map(|item| item.name.clone()).collect()
+    }
+
+    /// blog:     Rust, Tutorial
+    /// video:    Rust, Beginner
+    /// paper:    Concurrency
+    /// workshop: Rust, Concurrency, Tutorial
+    fn build_test_catalog(registry: &mut TagRegistry) -> ItemCatalog {
+        let mut catalog = ItemCatalog::new();
+        let mut item = |name: &str, tags: &[&str]| {
+            let tags = tags.iter().map(|tag| registry.intern(tag)).collect();
+            catalog.insert(TaggedItem::new(name, tags));
+        };
+
+        item("blog", &["Rust", "Tutorial"]);
+        item("video", &["Rust", "Beginner"]);
+        item("paper", &["Concurrency"]);
+        item("workshop", &["Rust", "Concurrency", "Tutorial"]);
+
+        catalog
+    }
+
+    #[test]
+    fn test_with_tag() {
+        let mut registry = TagRegistry::new();
+        let catalog = build_test_catalog(&mut registry);
+
+        assert_eq!(catalog.len(), 4);
+        assert_eq!(
+            names(&catalog.with_tag("Rust")),
+            vec!["blog", "video", "workshop"]
+        );
+        assert!(catalog.with_tag("Missing").is_empty());
+    }
+
+    #[test]
+    fn test_boolean_queries() {
+        let mut registry = TagRegistry::new();
+        let catalog = build_test_catalog(&mut registry);
+
+        let all = TagQuery::new().all_of(&["Rust", "Tutorial"]);
+        assert_eq!(names(&catalog.query(&all)), vec!["blog", "workshop"]);
+
+        let any = TagQuery::new().any_of(&["Beginner", "Concurrency"]);
+        assert_eq!(
+            names(&catalog.query(&any)),
+            vec!["video", "paper", "workshop"]
+        );
+
+        let none = TagQuery::new().none_of(&["Rust"]);
+        assert_eq!(names(&catalog.query(&none)), vec!["paper"]);
+
+        let combined = TagQuery::new()
+            .all_of(&["Rust"])
+            .any_of(&["Tutorial", "Beginner"])
+            .none_of(&["Concurrency"]);
+        assert_eq!(names(&catalog.query(&combined)), vec!["blog", "video"]);
+
+        let impossible = TagQuery::new().all_of(&["Rust", "Missing"]);
+        assert!(catalog.query(&impossible).is_empty());
+    }
+
+    #[test]
+    fn test_query_does_not_clone_tags() {
+        let mut registry = TagRegistry::new();
+        let catalog = build_test_catalog(&mut registry);
+        let rust = registry.get("Rust").unwrap();
+        let before = Rc::strong_count(&rust);
+
+        let matches = catalog.query(&TagQuery::new().all_of(&["Rust"]));
+        assert_eq!(matches.len(), 3);
+        assert_eq!(Rc::strong_count(&rust), before);
+    }
+
+    #[test]
+    fn test_tag_usage() {
+        let mut registry = TagRegistry::new();
+        let catalog = build_test_catalog(&mut registry);
+
+        assert_eq!(
+            catalog.tag_usage(),
+            vec![
+                ("Beginner".to_string(), 1),
+                ("Concurrency".to_string(), 2),
+                ("Rust".to_string(), 3),
+                ("Tutorial".to_string(), 2),
+            ]
+        );
+
+        // An outside holder shows up in the count too
+        let _rust = registry.get("Rust").unwrap();
+        assert!(catalog.tag_usage().contains(&("Rust".to_string(), 4)));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "was not interned by the catalog's registry")]
+    fn test_tags_from_another_registry_are_rejected() {
+        let mut registry = TagRegistry::new();
+        let mut catalog = build_test_catalog(&mut registry);
+
+        let mut other = TagRegistry::new();
+        catalog.insert(TaggedItem::new("talk", vec![other.intern("Rust")]));
+    }
+}
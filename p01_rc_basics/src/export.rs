@@ -0,0 +1,238 @@
+//! Sharing-Preserving Export
+//!
+//! A small line-based text format for a set of `Item`s that keeps
+//! `Rc<Tag>` identity: every shared tag is written once, and items
+//! refer to it by id.
+//!
+//! ```text
+//! tag 0 Rust
+//! tag 1 Tutorial
+//! item 0 blog
+//! item 0 video
+//! item 1 intro
+//! ```
+//!
+//! Names run to the end of the line; `\`, newlines and carriage returns
+//! are escaped as `\\`, `\n` and `\r`.
+
+use std::{collections::HashMap, error::Error, fmt, rc::Rc};
+
+use crate::{Item, Tag};
+
+/// Why an export could not be imported. Line numbers start at 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportError {
+    UnknownRecord { line: usize },
+    MissingField { line: usize },
+    InvalidId { line: usize },
+    DuplicateTag { line: usize, id: usize },
+    UnknownTag { line: usize, id: usize },
+    InvalidEscape { line: usize },
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::UnknownRecord { line } => write!(f, "line {}: unknown record", line),
+            ImportError::MissingField { line } => write!(f, "line {}: missing field", line),
+            ImportError::InvalidId { line } => write!(f, "line {}: invalid tag id", line),
+            ImportError::DuplicateTag { line, id } => {
+                write!(f, "line {}: tag {} defined twice", line, id)
+            }
+            ImportError::UnknownTag { line, id } => {
+                write!(f, "line {}: tag {} is not defined", line, id)
+            }
+            ImportError::InvalidEscape { line } => write!(f, "line {}: invalid escape", line),
+        }
+    }
+}
+
+impl Error for ImportError {}
+
+/// Writes `items`, emitting each distinct `Rc<Tag>` allocation once.
+pub fn export_items(items: &[Item]) -> String {
+    let mut ids: HashMap<*const Tag, usize> = HashMap::new();
+    let mut tag_lines = String::new();
+    let mut item_lines = String::new();
+
+    for item in items {
+        let next_id = ids.len();
+        let id = *ids.entry(Rc::as_ptr(&item.tag)).or_insert_with(|| {
+            tag_lines.push_str(&format!("tag {} {}\n", next_id, escape(&item.tag.name)));
+            next_id
+        });
+
+        item_lines.push_str(&format!("item {} {}\n", id, escape(&item.name)));
+    }
+
+    tag_lines + &item_lines
+}
+
+/// Rebuilds items from `export_items` output, creating exactly one
+/// `Rc<Tag>` per exported tag.
+pub fn import_items(input: &str) -> Result<Vec<Item>, ImportError> {
+    let mut tags: HashMap<usize, Rc<Tag>> = HashMap::new();
+    let mut items = Vec::new();
+
+    for (idx, raw) in input.lines().enumerate() {
+        let line = idx + 1;
+        if raw.is_empty() {
+            continue;
+        }
+
+        let mut fields = raw.splitn(3, ' ');
+        let kind = fields.next().unwrap_or_default();
+        let id = fields
+            .next()
+            .ok_or(ImportError::MissingField { line })?
+            .parse::<usize>()
+            .map_err(|_| ImportError::InvalidId { line })?;
+        let name = unescape(fields.next().ok_or(ImportError::MissingField { line })?)
+            .ok_or(ImportError::InvalidEscape { line })?;
+
+        match kind {
+            "tag" => {
+                if tags.contains_key(&id) {
+                    return Err(ImportError::DuplicateTag { line, id });
+                }
+                tags.insert(id, Rc::new(Tag::new(&name)));
+            }
+            "item" => {
+                let tag = tags.get(&id).ok_or(ImportError::UnknownTag { line, id })?;
+                items.push(Item::new(&name, Rc::clone(tag)));
+            }
+            _ => return Err(ImportError::UnknownRecord { line }),
+        }
+    }
+
+    Ok(items)
+}
+
+fn escape(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    for ch in name.chars() {
+        match ch {
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            _ => result.push(ch),
+        }
+    }
+    result
+}
+
+fn unescape(field: &str) -> Option<String> {
+    let mut result = String::with_capacity(field.len());
+    let mut chars = field.chars();
+
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            result.push(ch);
+            continue;
+        }
+
+        match chars.next()? {
+            '\\' => result.push('\\'),
+            'n' => result.push('\n'),
+            'r' => result.push('\r'),
+            _ => return None,
+        }
+    }
+
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_writes_each_tag_once() {
+        let rust = Rc::new(Tag::new("Rust"));
+        let tutorial = Rc::new(Tag::new("Tutorial"));
+        let items = vec![
+            Item::new("blog", Rc::clone(&rust)),
+            Item::new("intro", Rc::clone(&tutorial)),
+            Item::new("video", Rc::clone(&rust)),
+        ];
+
+        assert_eq!(
+            export_items(&items),
+            "tag 0 Rust\ntag 1 Tutorial\nitem 0 blog\nitem 1 intro\nitem 0 video\n"
+        );
+    }
+
+    #[test]
+    fn test_round_trip_preserves_sharing() {
+        let rust = Rc::new(Tag::new("Rust"));
+        let items: Vec<Item> = (1..=4)
+            .map(|idx| Item::new(&format!("Item {}", idx), Rc::clone(&rust)))
+            .chain([Item::new("lonely", Rc::new(Tag::new("Misc")))])
+            .collect();
+        drop(rust);
+
+        let imported = import_items(&export_items(&items)).unwrap();
+
+        assert_eq!(imported.len(), 5);
+        for (original, copy) in items.iter().zip(&imported) {
+            assert_eq!(original.name, copy.name);
+            assert_eq!(original.tag.name, copy.tag.name);
+            assert_eq!(Rc::strong_count(&original.tag), Rc::strong_count(&copy.tag));
+        }
+        assert!(Rc::ptr_eq(&imported[0].tag, &imported[3].tag));
+        assert!(!Rc::ptr_eq(&imported[0].tag, &imported[4].tag));
+    }
+
+    #[test]
+    fn test_same_name_different_allocations_stay_distinct() {
+        let items = vec![
+            Item::new("a", Rc::new(Tag::new("Rust"))),
+            Item::new("b", Rc::new(Tag::new("Rust"))),
+        ];
+
+        let imported = import_items(&export_items(&items)).unwrap();
+        assert!(!Rc::ptr_eq(&imported[0].tag, &imported[1].tag));
+    }
+
+    #[test]
+    fn test_names_with_special_characters() {
+        let tag = Rc::new(Tag::new("C:\\path with spaces\nand newline"));
+        let items = vec![Item::new(" padded ", tag)];
+
+        let imported = import_items(&export_items(&items)).unwrap();
+        assert_eq!(imported[0].name, " padded ");
+        assert_eq!(imported[0].tag.name, "C:\\path with spaces\nand newline");
+    }
+
+    #[test]
+    fn test_import_errors() {
+        assert_eq!(
+            import_items("item 0 blog").unwrap_err(),
+            ImportError::UnknownTag { line: 1, id: 0 }
+        );
+        assert_eq!(
+            import_items("tag 0 Rust\ntag 0 Again").unwrap_err(),
+            ImportError::DuplicateTag { line: 2, id: 0 }
+        );
+        assert_eq!(
+            import_items("tag x Rust").unwrap_err(),
+            ImportError::InvalidId { line: 1 }
+        );
+        assert_eq!(
+            import_items("tag 0").unwrap_err(),
+            ImportError::MissingField { line: 1 }
+        );
+        assert_eq!(
+            import_items("\nwidget 0 Rust").unwrap_err(),
+            ImportError::UnknownRecord { line: 2 }
+        );
+        assert_eq!(
+            import_items("tag 0 bad\\q").unwrap_err(),
+            ImportError::InvalidEscape { line: 1 }
+        );
+        assert_eq!(
+            ImportError::UnknownTag { line: 3, id: 7 }.to_string(),
+            "line 3: tag 7 is not defined"
+        );
+    }
+}
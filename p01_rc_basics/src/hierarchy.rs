@@ -0,0 +1,309 @@
+//! Hierarchical Tags
+//!
+//! Tags like "Rust/Concurrency/Beginner": a parent owns its children
+//! through `Rc`, and each child points back to its parent through `Weak`,
+//! so the hierarchy never forms a reference cycle.
+
+use std::{
+    ptr,
+    rc::{Rc, Weak},
+};
+
+use crate::Tag;
+
+/// Separator used when rendering and parsing tag paths.
+pub const PATH_SEPARATOR: char = '/';
+
+impl Tag {
+    /// Returns the child of `parent` named `name`, creating it if needed.
+    pub fn add_child(parent: &Rc<Tag>, name: &str) -> Rc<Tag> {
+        if let Some(existing) = parent.child(name) {
+            return existing;
+        }
+
+        let child = Rc::new(Tag::new(name));
+        *child.parent.borrow_mut() = Some(Rc::downgrade(parent));
+        parent.children.borrow_mut().push(Rc::clone(&child));
+        child
+    }
+
+    /// Detaches the child named `name`, returning the last owning reference
+    /// held by the hierarchy. Dropping it frees the whole subtree.
+    pub fn remove_child(&self, name: &str) -> Option<Rc<Tag>> {
+        let mut children = self.children.borrow_mut();
+        let idx = children.iter().position(|child| child.name == name)?;
+        let child = children.remove(idx);
+        *child.parent.borrow_mut() = None;
+        Some(child)
+    }
+
+    pub fn parent(&self) -> Option<Rc<Tag>> {
+        self.parent.borrow().as_ref().and_then(Weak::upgrade)
+    }
+
+    pub fn child(&self, name: &str) -> Option<Rc<Tag>> {
+        self.children
+            .borrow()
+            .iter()
+            .find(|child| child.name == name)
+            .cloned()
+    }
+
+    pub fn children(&self) -> Vec<Rc<Tag>> {
+        self.children.borrow().clone()
+    }
+
+    /// Ancestors from the direct parent up to the root.
+    pub fn ancestors(&self) -> Vec<Rc<Tag>> {
+        let mut result = Vec::new();
+        let mut curr = self.parent();
+
+        while let Some(tag) = curr {
+            curr = tag.parent();
+            result.push(tag);
+        }
+
+        result
+    }
+
+    /// All descendants in pre-order, not including `self`.
+    pub fn descendants(&self) -> Vec<Rc<Tag>> {
+        let mut result = Vec::new();
+        let mut stack: Vec<Rc<Tag>> = self.children().into_iter().rev().collect();
+
+        while let Some(tag) = stack.pop() {
+            stack.extend(tag.children().into_iter().rev());
+            result.push(tag);
+        }
+
+        result
+    }
+
+    /// Renders the full path, e.g. "Rust/Concurrency/Beginner".
+    pub fn path(&self) -> String {
+        let mut segments: Vec<String> = self
+            .ancestors()
+            .iter()
+            .map(|tag| tag.name.clone())
+            .collect();
+        segments.reverse();
+        segments.push(self.name.clone());
+        segments.join(&PATH_SEPARATOR.to_string())
+    }
+
+    /// True if `self` is `other` or sits somewhere below it.
+    pub fn is_within(&self, other: &Tag) -> bool {
+        ptr::eq(self, other)
+            || self
+                .ancestors()
+                .iter()
+                .any(|ancestor| ptr::eq(Rc::as_ptr(ancestor), other))
+    }
+}
+
+/// Owns the root tags of a hierarchy.
+#[derive(Debug, Default)]
+pub struct TagTree {
+    roots: Vec<Rc<Tag>>,
+}
+
+impl TagTree {
+    pub fn new() -> Self {
+        TagTree { roots: Vec::new() }
+    }
+
+    /// Returns the tag at `path`, creating any missing segments.
+    ///
+    /// Panics if `path` has no segments.
+    pub fn insert_path(&mut self, path: &str) -> Rc<Tag> {
+        let mut segments = split_path(path);
+        let first = segments
+            .next()
+            .expect("tag path should have at least one segment");
+
+        let mut curr = match self.root(first) {
+            Some(root) => root,
+            None => {
+                let root = Rc::new(Tag::new(first));
+                self.roots.push(Rc::clone(&root));
+                root
+            }
+        };
+
+        for segment in segments {
+            curr = Tag::add_child(&curr, segment);
+        }
+
+        curr
+    }
+
+    pub fn find(&self, path: &str) -> Option<Rc<Tag>> {
+        let mut segments = split_path(path);
+        let mut curr = self.root(segments.next()?)?;
+
+        for segment in segments {
+            curr = curr.child(segment)?;
+        }
+
+        Some(curr)
+    }
+
+    /// Removes the tag at `path` together with its subtree.
+    /// Returns false if no such tag exists.
+    pub fn remove(&mut self, path: &str) -> bool {
+        let Some(tag) = self.find(path) else {
+            return false;
+        };
+
+        match tag.parent() {
+            Some(parent) => {
+                parent.remove_child(&tag.name);
+            }
+            None => self.roots.retain(|root| !Rc::ptr_eq(root, &tag)),
+        }
+
+        true
+    }
+
+    pub fn roots(&self) -> &[Rc<Tag>] {
+        &self.roots
+    }
+
+    fn root(&self, name: &str) -> Option<Rc<Tag>> {
+        self.roots.iter().find(|root| root.name == name).cloned()
+    }
+}
+
+fn split_path(path: &str) -> impl Iterator<Item = &str> {
+    path.split(PATH_SEPARATOR)
+        .filter(|segment| !segment.is_empty())
+}
+
+/// An item that refers to a tag in a `TagTree` without owning it.
+/// If the tag's subtree is removed, the item's tag simply disappears.
+#[derive(Debug)]
+pub struct TreeItem {
+    pub name: String,
+    pub tag: Weak<Tag>,
+}
+
+impl TreeItem {
+    pub fn new(name: &str, tag: &Rc<Tag>) -> Self {
+        TreeItem {
+            name: name.to_string(),
+            tag: Rc::downgrade(tag),
+        }
+    }
+
+    pub fn tag(&self) -> Option<Rc<Tag>> {
+        self.tag.upgrade()
+    }
+
+    /// True if the item's tag is `tag` or any descendant of it,
+    /// i.e. `tag` is the item's tag or one of its ancestors.
+    pub fn matches(&self, tag: &Tag) -> bool {
+        self.tag().is_some_and(|own| own.is_within(tag))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds:
+    ///   Rust
+    ///   ├── Concurrency
+    ///   │   ├── Beginner
+    ///   │   └── Advanced
+    ///   └── Macros
+    ///   Python
+    fn build_test_tree() -> TagTree {
+        let mut tree = TagTree::new();
+        tree.insert_path("Rust/Concurrency/Beginner");
+        tree.insert_path("Rust/Concurrency/Advanced");
+        tree.insert_path("Rust/Macros");
+        tree.insert_path("Python");
+        tree
+    }
+
+    fn tag_names(tags: &[Rc<Tag>]) -> Vec<String> {
+        tags.iter().map(|tag| tag.name.clone()).collect()
+    }
+
+    #[test]
+    fn test_insert_and_find_paths() {
+        let mut tree = build_test_tree();
+
+        assert_eq!(tag_names(tree.roots()), vec!["Rust", "Python"]);
+
+        let beginner = tree.find("Rust/Concurrency/Beginner").unwrap();
+        assert_eq!(beginner.path(), "Rust/Concurrency/Beginner");
+
+        // Inserting an existing path reuses the same tag
+        let again = tree.insert_path("Rust/Concurrency/Beginner");
+        assert!(Rc::ptr_eq(&beginner, &again));
+
+        assert!(tree.find("Rust/Missing").is_none());
+        assert!(tree.find("").is_none());
+    }
+
+    #[test]
+    fn test_ancestors_and_descendants() {
+        let tree = build_test_tree();
+        let rust = tree.find("Rust").unwrap();
+        let beginner = tree.find("Rust/Concurrency/Beginner").unwrap();
+
+        assert_eq!(
+            tag_names(&beginner.ancestors()),
+            vec!["Concurrency", "Rust"]
+        );
+        assert!(rust.ancestors().is_empty());
+        assert_eq!(
+            tag_names(&rust.descendants()),
+            vec!["Concurrency", "Beginner", "Advanced", "Macros"]
+        );
+
+        // Parent links are Weak: only the tree and our handle own `rust`
+        assert_eq!(Rc::strong_count(&rust), 2);
+    }
+
+    #[test]
+    fn test_item_matches_tag_or_ancestor() {
+        let tree = build_test_tree();
+        let beginner = tree.find("Rust/Concurrency/Beginner").unwrap();
+        let item = TreeItem::new("intro to threads", &beginner);
+
+        assert!(item.matches(&beginner));
+        assert!(item.matches(&tree.find("Rust/Concurrency").unwrap()));
+        assert!(item.matches(&tree.find("Rust").unwrap()));
+        assert!(!item.matches(&tree.find("Rust/Concurrency/Advanced").unwrap()));
+        assert!(!item.matches(&tree.find("Python").unwrap()));
+    }
+
+    #[test]
+    fn test_removed_subtree_is_freed() {
+        let mut tree = build_test_tree();
+
+        let item = {
+            let beginner = tree.find("Rust/Concurrency/Beginner").unwrap();
+            TreeItem::new("intro to threads", &beginner)
+        };
+        let concurrency = Rc::downgrade(&tree.find("Rust/Concurrency").unwrap());
+
+        assert!(tree.remove("Rust/Concurrency"));
+        assert!(!tree.remove("Rust/Concurrency"));
+
+        // Item handles do not keep the subtree alive
+        assert!(concurrency.upgrade().is_none());
+        assert!(item.tag().is_none());
+        assert!(!item.matches(&tree.find("Rust").unwrap()));
+
+        assert_eq!(
+            tag_names(&tree.find("Rust").unwrap().children()),
+            vec!["Macros"]
+        );
+
+        assert!(tree.remove("Python"));
+        assert_eq!(tag_names(tree.roots()), vec!["Rust"]);
+    }
+}
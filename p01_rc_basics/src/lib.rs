@@ -1,16 +1,37 @@
-use std::rc::Rc;
+pub mod catalog;
+pub mod export;
+pub mod hierarchy;
+pub mod metadata;
+pub mod registry;
+
+use std::{
+    cell::RefCell,
+    rc::{Rc, Weak},
+};
+
+use metadata::TagMeta;
 
 /// A tag that can be shared across multiple items.
 /// Example: "Rust", "Tutorial", "Beginner"
+///
+/// Tags can also form a hierarchy: a parent owns its children,
+/// and each child points back through a `Weak` (see `hierarchy`).
+/// Description, color and renames live behind a `RefCell` (see `metadata`).
 #[derive(Debug)]
 pub struct Tag {
     pub name: String,
+    parent: RefCell<Option<Weak<Tag>>>,
+    children: RefCell<Vec<Rc<Tag>>>,
+    meta: RefCell<TagMeta>,
 }
 
 impl Tag {
     pub fn new(name: &str) -> Self {
         Tag {
             name: name.to_string(),
+            parent: RefCell::new(None),
+            children: RefCell::new(Vec::new()),
+            meta: RefCell::new(TagMeta::default()),
         }
     }
 }
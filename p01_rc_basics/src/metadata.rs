@@ -0,0 +1,182 @@
+//! Shared Tag Metadata
+//!
+//! Interior-mutable metadata on a shared `Tag`. Because every item holds
+//! the same `Rc<Tag>`, an edit made through any of them is visible to all.
+//! Each edit is appended to a per-tag change log.
+//!
+//! `Tag::name` stays fixed: it is the key used by the registry, catalog
+//! and hierarchy. A rename changes the tag's `display_name` instead.
+
+use std::cell::Ref;
+
+use crate::Tag;
+
+/// One recorded edit to a tag's metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagChange {
+    Renamed {
+        from: String,
+        to: String,
+    },
+    DescriptionSet {
+        from: Option<String>,
+        to: Option<String>,
+    },
+    ColorSet {
+        from: Option<String>,
+        to: Option<String>,
+    },
+}
+
+/// Mutable state stored behind the tag's `RefCell`.
+#[derive(Debug, Default)]
+pub(crate) struct TagMeta {
+    display_name: Option<String>,
+    description: Option<String>,
+    color: Option<String>,
+    changes: Vec<TagChange>,
+}
+
+impl Tag {
+    /// The current name: the latest rename, or `name` if never renamed.
+    pub fn display_name(&self) -> String {
+        self.meta
+            .borrow()
+            .display_name
+            .clone()
+            .unwrap_or_else(|| self.name.clone())
+    }
+
+    pub fn description(&self) -> Option<String> {
+        self.meta.borrow().description.clone()
+    }
+
+    pub fn color(&self) -> Option<String> {
+        self.meta.borrow().color.clone()
+    }
+
+    /// Renames the tag for every item sharing it. No-op if unchanged.
+    pub fn rename(&self, new_name: &str) {
+        let from = self.display_name();
+        if from == new_name {
+            return;
+        }
+
+        let mut meta = self.meta.borrow_mut();
+        meta.display_name = Some(new_name.to_string());
+        meta.changes.push(TagChange::Renamed {
+            from,
+            to: new_name.to_string(),
+        });
+    }
+
+    /// Sets or clears the description. No-op if unchanged.
+    pub fn set_description(&self, description: Option<&str>) {
+        let mut meta = self.meta.borrow_mut();
+        let to = description.map(str::to_string);
+        if meta.description == to {
+            return;
+        }
+
+        let from = std::mem::replace(&mut meta.description, to.clone());
+        meta.changes.push(TagChange::DescriptionSet { from, to });
+    }
+
+    /// Sets or clears the color, e.g. "#dea584". No-op if unchanged.
+    pub fn set_color(&self, color: Option<&str>) {
+        let mut meta = self.meta.borrow_mut();
+        let to = color.map(str::to_string);
+        if meta.color == to {
+            return;
+        }
+
+        let from = std::mem::replace(&mut meta.color, to.clone());
+        meta.changes.push(TagChange::ColorSet { from, to });
+    }
+
+    /// All edits so far, oldest first.
+    ///
+    /// The returned `Ref` keeps the metadata borrowed; drop it before
+    /// editing the tag again.
+    pub fn changes(&self) -> Ref<'_, [TagChange]> {
+        Ref::map(self.meta.borrow(), |meta| meta.changes.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::Item;
+
+    #[test]
+    fn test_edits_are_visible_through_every_item() {
+        let tag = Rc::new(Tag::new("Rust"));
+        let blog = Item::new("blog", Rc::clone(&tag));
+        let video = Item::new("video", Rc::clone(&tag));
+
+        blog.tag.rename("Rust Lang");
+        video.tag.set_color(Some("#dea584"));
+        blog.tag.set_description(Some("Systems programming"));
+
+        for item in [&blog, &video] {
+            assert_eq!(item.tag.display_name(), "Rust Lang");
+            assert_eq!(item.tag.color().as_deref(), Some("#dea584"));
+            assert_eq!(
+                item.tag.description().as_deref(),
+                Some("Systems programming")
+            );
+            // The identity key is untouched
+            assert_eq!(item.tag.name, "Rust");
+        }
+    }
+
+    #[test]
+    fn test_change_log_records_each_edit() {
+        let tag = Tag::new("Rust");
+
+        tag.rename("Rust Lang");
+        tag.rename("Rust Lang"); // unchanged, not logged
+        tag.set_color(Some("#dea584"));
+        tag.set_color(None);
+        tag.set_description(Some("Systems programming"));
+        tag.rename("Rust");
+
+        assert_eq!(
+            *tag.changes(),
+            [
+                TagChange::Renamed {
+                    from: "Rust".to_string(),
+                    to: "Rust Lang".to_string(),
+                },
+                TagChange::ColorSet {
+                    from: None,
+                    to: Some("#dea584".to_string()),
+                },
+                TagChange::ColorSet {
+                    from: Some("#dea584".to_string()),
+                    to: None,
+                },
+                TagChange::DescriptionSet {
+                    from: None,
+                    to: Some("Systems programming".to_string()),
+                },
+                TagChange::Renamed {
+                    from: "Rust Lang".to_string(),
+                    to: "Rust".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_new_tag_has_no_metadata() {
+        let tag = Tag::new("Beginner");
+
+        assert_eq!(tag.display_name(), "Beginner");
+        assert!(tag.description().is_none());
+        assert!(tag.color().is_none());
+        assert!(tag.changes().is_empty());
+    }
+}
@@ -0,0 +1,140 @@
+//! Tag Interning
+//!
+//! A registry that hands out one shared `Rc<Tag>` per tag name.
+//! The registry only keeps `Weak` references, so a tag is freed as soon
+//! as the last item using it is dropped.
+
+use std::{
+    collections::HashMap,
+    rc::{Rc, Weak},
+};
+
+use crate::Tag;
+
+/// Deduplicates tags by name without keeping them alive.
+#[derive(Debug, Default)]
+pub struct TagRegistry {
+    tags: HashMap<String, Weak<Tag>>,
+}
+
+impl TagRegistry {
+    pub fn new() -> Self {
+        TagRegistry {
+            tags: HashMap::new(),
+        }
+    }
+
+    /// Returns the shared tag for `name`, creating it if no live tag exists.
+    pub fn intern(&mut self, name: &str) -> Rc<Tag> {
+        if let Some(tag) = self.get(name) {
+            return tag;
+        }
+
+        let tag = Rc::new(Tag::new(name));
+        self.tags.insert(name.to_string(), Rc::downgrade(&tag));
+        tag
+    }
+
+    /// Returns the shared tag for `name` only if it is still alive.
+    pub fn get(&self, name: &str) -> Option<Rc<Tag>> {
+        self.tags.get(name).and_then(Weak::upgrade)
+    }
+
+    /// Drops entries whose tag has been freed. Returns how many were removed.
+    pub fn purge(&mut self) -> usize {
+        let before = self.tags.len();
+        self.tags.retain(|_, weak| weak.strong_count() > 0);
+        before - self.tags.len()
+    }
+
+    /// Number of tags that are still alive.
+    pub fn len(&self) -> usize {
+        self.tags
+            .values()
+            .filter(|weak| weak.strong_count() > 0)
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Names of the tags that are still alive, sorted.
+    pub fn live_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .tags
+            .iter()
+            .filter(|(_, weak)| weak.strong_count() > 0)
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Item;
+
+    #[test]
+    fn test_intern_returns_same_allocation() {
+        let mut registry = TagRegistry::new();
+
+        let first = registry.intern("Rust");
+        let second = registry.intern("Rust");
+        let other = registry.intern("Tutorial");
+
+        assert!(Rc::ptr_eq(&first, &second));
+        assert!(!Rc::ptr_eq(&first, &other));
+        assert_eq!(Rc::strong_count(&first), 2);
+        assert_eq!(registry.len(), 2);
+    }
+
+    #[test]
+    fn test_items_share_interned_tag() {
+        let mut registry = TagRegistry::new();
+
+        let blog = Item::new("blog", registry.intern("Rust"));
+        let video = Item::new("video", registry.intern("Rust"));
+
+        assert!(Rc::ptr_eq(&blog.tag, &video.tag));
+        // The registry itself holds no strong reference
+        assert_eq!(Rc::strong_count(&blog.tag), 2);
+    }
+
+    #[test]
+    fn test_unused_tags_are_freed_and_purged() {
+        let mut registry = TagRegistry::new();
+
+        let rust = registry.intern("Rust");
+        {
+            let _beginner = registry.intern("Beginner");
+            assert_eq!(registry.live_names(), vec!["Beginner", "Rust"]);
+        }
+
+        // "Beginner" is dead but its entry is still in the map
+        assert_eq!(registry.len(), 1);
+        assert!(registry.get("Beginner").is_none());
+        assert_eq!(registry.live_names(), vec!["Rust"]);
+
+        assert_eq!(registry.purge(), 1);
+        assert_eq!(registry.purge(), 0);
+
+        drop(rust);
+        assert!(registry.is_empty());
+        assert_eq!(registry.purge(), 1);
+    }
+
+    #[test]
+    fn test_intern_recreates_freed_tag() {
+        let mut registry = TagRegistry::new();
+
+        let weak = Rc::downgrade(&registry.intern("Rust"));
+        assert!(weak.upgrade().is_none());
+
+        let tag = registry.intern("Rust");
+        assert_eq!(tag.name, "Rust");
+        assert_eq!(registry.len(), 1);
+    }
+}
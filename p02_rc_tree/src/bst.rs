@@ -0,0 +1,235 @@
+//! Persistent Binary Search Tree
+//!
+//! `insert` and `remove` never modify a node. They copy the path from the
+//! root to the change and return a new root, reusing every untouched subtree
+//! through `Rc::clone`. Old roots stay valid, so several versions of the
+//! tree can be kept alive at once.
+//!
+//! Walks use an explicit path instead of recursion, so an unbalanced tree
+//! built from sorted input cannot overflow the stack.
+
+use std::{cmp::Ordering, rc::Rc};
+
+use crate::Node;
+
+/// Returns true if `value` is in the tree.
+pub fn contains<T: Ord>(root: Option<&Rc<Node<T>>>, value: &T) -> bool {
+    let mut curr = root;
+
+    while let Some(node) = curr {
+        curr = match value.cmp(&node.value) {
+            Ordering::Less => node.left.as_ref(),
+            Ordering::Greater => node.right.as_ref(),
+            Ordering::Equal => return true,
+        };
+    }
+
+    false
+}
+
+/// Returns the root of a new version containing `value`.
+/// If `value` is already present, the old root is returned unchanged.
+pub fn insert<T: Ord + Clone>(root: Option<&Rc<Node<T>>>, value: T) -> Rc<Node<T>> {
+    let mut path = Vec::new();
+    let mut curr = root;
+
+    while let Some(node) = curr {
+        let went_left = match value.cmp(&node.value) {
+            Ordering::Less => true,
+            Ordering::Greater => false,
+            Ordering::Equal => return Rc::clone(root.expect("walk starts at the root")),
+        };
+        curr = if went_left {
+            node.left.as_ref()
+        } else {
+            node.right.as_ref()
+        };
+        path.push((node, went_left));
+    }
+
+    rebuild_path(path, Some(Rc::new(Node::new(value)))).expect("inserted tree is never empty")
+}
+
+/// Returns the root of a new version without `value`.
+/// If `value` is absent, the old root is returned unchanged.
+pub fn remove<T: Ord + Clone>(root: Option<&Rc<Node<T>>>, value: &T) -> Option<Rc<Node<T>>> {
+    let mut path = Vec::new();
+    let mut curr = root;
+
+    let target = loop {
+        let Some(node) = curr else {
+            return root.cloned();
+        };
+        let went_left = match value.cmp(&node.value) {
+            Ordering::Less => true,
+            Ordering::Greater => false,
+            Ordering::Equal => break node,
+        };
+        curr = if went_left {
+            node.left.as_ref()
+        } else {
+            node.right.as_ref()
+        };
+        path.push((node, went_left));
+    };
+
+    let replacement = match (&target.left, &target.right) {
+        (None, right) => right.clone(),
+        (left, None) => left.clone(),
+        (Some(left), Some(right)) => {
+            // Replace with the in-order successor, removed from the right subtree
+            let (successor, new_right) = remove_min(right);
+            Some(Rc::new(Node {
+                value: successor.clone(),
+                left: Some(Rc::clone(left)),
+                right: new_right,
+            }))
+        }
+    };
+
+    rebuild_path(path, replacement)
+}
+
+/// Removes the smallest value under `root`.
+/// Returns that value and the root of the remaining subtree.
+fn remove_min<T: Clone>(root: &Rc<Node<T>>) -> (&T, Option<Rc<Node<T>>>) {
+    let mut path = Vec::new();
+    let mut curr = root;
+
+    while let Some(left) = curr.left.as_ref() {
+        path.push((curr, true));
+        curr = left;
+    }
+
+    (&curr.value, rebuild_path(path, curr.right.clone()))
+}
+
+/// Copies each node on `path` (root first) bottom-up, hanging `child` where
+/// the walk left off. Siblings off the path are shared, not copied.
+fn rebuild_path<T: Clone>(
+    path: Vec<(&Rc<Node<T>>, bool)>,
+    child: Option<Rc<Node<T>>>,
+) -> Option<Rc<Node<T>>> {
+    path.into_iter()
+        .rev()
+        .fold(child, |child, (node, went_left)| {
+            let (left, right) = if went_left {
+                (child, node.right.clone())
+            } else {
+                (node.left.clone(), child)
+            };
+
+            Some(Rc::new(Node {
+                value: node.value.clone(),
+                left,
+                right,
+            }))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{count_nodes, traversal};
+
+    fn build(values: &[i32]) -> Option<Rc<Node<i32>>> {
+        values
+            .iter()
+            .fold(None, |root, &value| Some(insert(root.as_ref(), value)))
+    }
+
+    fn sorted(root: Option<&Rc<Node<i32>>>) -> Vec<i32> {
+        root.map(|root| traversal::in_order(root).map(|node| node.value).collect())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_insert_and_contains() {
+        let root = build(&[50, 30, 70, 20, 40, 60, 80]);
+
+        assert_eq!(sorted(root.as_ref()), [20, 30, 40, 50, 60, 70, 80]);
+        assert!(contains(root.as_ref(), &40));
+        assert!(!contains(root.as_ref(), &45));
+        assert!(!contains(None, &45));
+    }
+
+    #[test]
+    fn test_insert_shares_untouched_subtrees() {
+        let v1 = build(&[50, 30, 70, 20, 40]).unwrap();
+        let v2 = insert(Some(&v1), 60);
+
+        // Old version is unchanged
+        assert_eq!(sorted(Some(&v1)), [20, 30, 40, 50, 70]);
+        assert_eq!(sorted(Some(&v2)), [20, 30, 40, 50, 60, 70]);
+
+        // Only the path 50 -> 70 was copied; the whole left side is shared
+        assert!(!Rc::ptr_eq(&v1, &v2));
+        assert!(Rc::ptr_eq(
+            v1.left.as_ref().unwrap(),
+            v2.left.as_ref().unwrap()
+        ));
+        assert!(!Rc::ptr_eq(
+            v1.right.as_ref().unwrap(),
+            v2.right.as_ref().unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_insert_existing_returns_same_root() {
+        let v1 = build(&[2, 1, 3]).unwrap();
+        let v2 = insert(Some(&v1), 3);
+
+        assert!(Rc::ptr_eq(&v1, &v2));
+    }
+
+    #[test]
+    fn test_remove_leaf_and_inner_nodes() {
+        let v1 = build(&[50, 30, 70, 20, 40, 60, 80]);
+
+        let v2 = remove(v1.as_ref(), &20);
+        let v3 = remove(v2.as_ref(), &70);
+        let v4 = remove(v3.as_ref(), &50);
+
+        assert_eq!(sorted(v1.as_ref()), [20, 30, 40, 50, 60, 70, 80]);
+        assert_eq!(sorted(v2.as_ref()), [30, 40, 50, 60, 70, 80]);
+        assert_eq!(sorted(v3.as_ref()), [30, 40, 50, 60, 80]);
+        assert_eq!(sorted(v4.as_ref()), [30, 40, 60, 80]);
+
+        // Removing the root's successor path leaves the left side shared
+        let (v3, v4) = (v3.unwrap(), v4.unwrap());
+        assert_eq!(v4.value, 60);
+        assert!(Rc::ptr_eq(
+            v3.left.as_ref().unwrap(),
+            v4.left.as_ref().unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_remove_missing_and_last() {
+        let v1 = build(&[1]);
+
+        let same = remove(v1.as_ref(), &2).unwrap();
+        assert!(Rc::ptr_eq(v1.as_ref().unwrap(), &same));
+
+        assert!(remove(v1.as_ref(), &1).is_none());
+        assert!(remove(None, &1).is_none());
+    }
+
+    #[test]
+    fn test_version_history_stays_valid() {
+        let mut history = vec![None];
+        for value in [5, 2, 8, 1, 9, 3] {
+            let next = insert(history.last().unwrap().as_ref(), value);
+            history.push(Some(next));
+        }
+        let next = remove(history.last().unwrap().as_ref(), &5);
+        history.push(next);
+
+        let sizes: Vec<usize> = history
+            .iter()
+            .map(|root| root.as_ref().map_or(0, count_nodes))
+            .collect();
+        assert_eq!(sizes, [0, 1, 2, 3, 4, 5, 6, 5]);
+        assert_eq!(sorted(history[4].as_ref()), [1, 2, 5, 8]);
+    }
+}
@@ -0,0 +1,219 @@
+//! Structural Diff
+//!
+//! Compares two versions of a tree. Persistent updates share every
+//! untouched subtree, so whenever both sides point at the same allocation
+//! (`Rc::ptr_eq`) the whole subtree is skipped. The work done is therefore
+//! proportional to the changed region, not to the size of the tree.
+
+use std::rc::Rc;
+
+use crate::{Node, Side};
+
+/// One difference between two trees, located by the sides taken from the root.
+#[derive(Debug, Clone)]
+pub enum Edit<T> {
+    /// The node exists in both trees with a different value.
+    Changed { path: Vec<Side>, old: T, new: T },
+    /// A subtree exists only in the new tree.
+    Added {
+        path: Vec<Side>,
+        subtree: Rc<Node<T>>,
+    },
+    /// A subtree exists only in the old tree.
+    Removed {
+        path: Vec<Side>,
+        subtree: Rc<Node<T>>,
+    },
+}
+
+impl<T> Edit<T> {
+    pub fn path(&self) -> &[Side] {
+        match self {
+            Edit::Changed { path, .. } | Edit::Added { path, .. } | Edit::Removed { path, .. } => {
+                path
+            }
+        }
+    }
+}
+
+enum Step<'a, T> {
+    Visit {
+        old: Option<&'a Rc<Node<T>>>,
+        new: Option<&'a Rc<Node<T>>>,
+        side: Option<Side>,
+    },
+    Leave,
+}
+
+/// Lists the edits turning `old` into `new`, in pre-order (left before right).
+pub fn diff<T: PartialEq + Clone>(old: &Rc<Node<T>>, new: &Rc<Node<T>>) -> Vec<Edit<T>> {
+    let mut edits = Vec::new();
+    let mut path = Vec::new();
+    let mut stack = vec![Step::Visit {
+        old: Some(old),
+        new: Some(new),
+        side: None,
+    }];
+
+    while let Some(step) = stack.pop() {
+        let Step::Visit { old, new, side } = step else {
+            path.pop();
+            continue;
+        };
+
+        path.extend(side);
+        stack.push(Step::Leave);
+
+        match (old, new) {
+            (Some(old), Some(new)) if Rc::ptr_eq(old, new) => {}
+            (None, None) => {}
+            (None, Some(new)) => edits.push(Edit::Added {
+                path: path.clone(),
+                subtree: Rc::clone(new),
+            }),
+            (Some(old), None) => edits.push(Edit::Removed {
+                path: path.clone(),
+                subtree: Rc::clone(old),
+            }),
+            (Some(old), Some(new)) => {
+                if old.value != new.value {
+                    edits.push(Edit::Changed {
+                        path: path.clone(),
+                        old: old.value.clone(),
+                        new: new.value.clone(),
+                    });
+                }
+
+                stack.push(Step::Visit {
+                    old: old.right.as_ref(),
+                    new: new.right.as_ref(),
+                    side: Some(Side::Right),
+                });
+                stack.push(Step::Visit {
+                    old: old.left.as_ref(),
+                    new: new.left.as_ref(),
+                    side: Some(Side::Left),
+                });
+            }
+        }
+    }
+
+    edits
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::{text, zipper::Zipper};
+
+    fn describe(edits: &[Edit<String>]) -> Vec<String> {
+        edits
+            .iter()
+            .map(|edit| {
+                let path: String = edit
+                    .path()
+                    .iter()
+                    .map(|side| match side {
+                        Side::Left => 'L',
+                        Side::Right => 'R',
+                    })
+                    .collect();
+                match edit {
+                    Edit::Changed { old, new, .. } => format!("~{} {}->{}", path, old, new),
+                    Edit::Added { subtree, .. } => format!("+{} {}", path, text::to_sexpr(subtree)),
+                    Edit::Removed { subtree, .. } => {
+                        format!("-{} {}", path, text::to_sexpr(subtree))
+                    }
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_identical_trees_have_no_edits() {
+        let root: Rc<Node<String>> = text::parse_tree("(A (B D _) (C _ E))").unwrap();
+
+        assert!(diff(&root, &root).is_empty());
+
+        // Equal but unshared trees are compared node by node
+        let copy = text::parse_tree("(A (B D _) (C _ E))").unwrap();
+        assert!(diff(&root, &copy).is_empty());
+    }
+
+    #[test]
+    fn test_changed_added_and_removed() {
+        let old: Rc<Node<String>> = text::parse_tree("(A (B D _) (C _ E))").unwrap();
+        let new = text::parse_tree("(X (B _ (F G _)) (C _ Y))").unwrap();
+
+        assert_eq!(
+            describe(&diff(&old, &new)),
+            ["~ A->X", "-LL D", "+LR (F G _)", "~RR E->Y"]
+        );
+    }
+
+    #[test]
+    fn test_diff_between_zipper_versions() {
+        let old: Rc<Node<String>> = text::parse_tree("(A (B D _) (C _ E))").unwrap();
+
+        let mut zipper = Zipper::new(Rc::clone(&old));
+        zipper.go_right();
+        zipper.set_child(Side::Left, Some(Rc::new(Node::new("N".to_string()))));
+        zipper.go_right();
+        zipper.replace_value("E2".to_string());
+        let new = zipper.to_root();
+
+        assert_eq!(describe(&diff(&old, &new)), ["+RL N", "~RR E->E2"]);
+        assert_eq!(describe(&diff(&new, &old)), ["-RL N", "~RR E2->E"]);
+    }
+
+    thread_local! {
+        static COMPARISONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    /// A value that counts how often it is compared.
+    #[derive(Debug, Clone)]
+    struct Counted(u32);
+
+    impl PartialEq for Counted {
+        fn eq(&self, other: &Self) -> bool {
+            COMPARISONS.with(|count| count.set(count.get() + 1));
+            self.0 == other.0
+        }
+    }
+
+    fn build_balanced(depth: u32, next: &mut u32) -> Rc<Node<Counted>> {
+        *next += 1;
+        let value = Counted(*next);
+        if depth == 1 {
+            return Rc::new(Node::new(value));
+        }
+        let left = build_balanced(depth - 1, next);
+        let right = build_balanced(depth - 1, next);
+        Rc::new(Node::with_children(value, left, right))
+    }
+
+    #[test]
+    fn test_diff_skips_shared_subtrees() {
+        // 2^16 - 1 nodes
+        let old = build_balanced(16, &mut 0);
+
+        let mut zipper = Zipper::new(Rc::clone(&old));
+        for _ in 0..7 {
+            zipper.go_left();
+            zipper.go_right();
+        }
+        zipper.go_left();
+        zipper.replace_value(Counted(0));
+        let new = zipper.to_root();
+
+        COMPARISONS.with(|count| count.set(0));
+        let edits = diff(&old, &new);
+
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].path().len(), 16 - 1);
+        // Only the 16 nodes on the copied spine were compared
+        assert_eq!(COMPARISONS.with(Cell::get), 16);
+    }
+}
@@ -0,0 +1,172 @@
+//! Hash-Consing Node Factory
+//!
+//! Builds nodes so that structurally equal subtrees are the same
+//! allocation. A node is identified by its value plus the pointer identity
+//! of its children; since children come from the same factory, equal
+//! children are already the same pointer, and two trees are equal exactly
+//! when `Rc::ptr_eq` says so.
+//!
+//! The table only holds `Weak` references, so nodes nobody uses are freed.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    hash::Hash,
+    rc::{Rc, Weak},
+};
+
+use crate::Node;
+
+/// Value plus child addresses (0 for a missing child).
+type NodeKey<T> = (T, usize, usize);
+
+/// Deduplicates nodes by value and children.
+///
+/// Building takes `&self` (the table sits in a `RefCell`), so calls can be
+/// nested: `factory.make(op, Some(factory.leaf(x)), None)`.
+#[derive(Debug)]
+pub struct NodeFactory<T> {
+    table: RefCell<HashMap<NodeKey<T>, Weak<Node<T>>>>,
+}
+
+impl<T: Eq + Hash + Clone> NodeFactory<T> {
+    pub fn new() -> Self {
+        NodeFactory {
+            table: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn leaf(&self, value: T) -> Rc<Node<T>> {
+        self.make(value, None, None)
+    }
+
+    /// Returns the existing node with this value and these exact children,
+    /// or creates it.
+    ///
+    /// Children should come from this factory; otherwise equal subtrees built
+    /// elsewhere are still told apart by address.
+    pub fn make(
+        &self,
+        value: T,
+        left: Option<Rc<Node<T>>>,
+        right: Option<Rc<Node<T>>>,
+    ) -> Rc<Node<T>> {
+        let key = (value.clone(), address(&left), address(&right));
+        let mut table = self.table.borrow_mut();
+
+        // A live entry keeps its children alive, so their addresses cannot
+        // have been reused by another node.
+        if let Some(existing) = table.get(&key).and_then(Weak::upgrade) {
+            return existing;
+        }
+
+        let node = Rc::new(Node { value, left, right });
+        table.insert(key, Rc::downgrade(&node));
+        node
+    }
+
+    /// Number of distinct nodes that are still alive.
+    pub fn len(&self) -> usize {
+        self.table
+            .borrow()
+            .values()
+            .filter(|weak| weak.strong_count() > 0)
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops entries whose node has been freed. Returns how many were removed.
+    pub fn purge(&self) -> usize {
+        let mut table = self.table.borrow_mut();
+        let before = table.len();
+        table.retain(|_, weak| weak.strong_count() > 0);
+        before - table.len()
+    }
+}
+
+impl<T: Eq + Hash + Clone> Default for NodeFactory<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn address<T>(child: &Option<Rc<Node<T>>>) -> usize {
+    child.as_ref().map_or(0, |child| Rc::as_ptr(child) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{count_nodes, sharing::count_unique_nodes};
+
+    /// Builds (x + y) * (x + y).
+    fn build_square(factory: &NodeFactory<&'static str>) -> Rc<Node<&'static str>> {
+        let sum = |f: &NodeFactory<_>| f.make("+", Some(f.leaf("x")), Some(f.leaf("y")));
+        factory.make("*", Some(sum(factory)), Some(sum(factory)))
+    }
+
+    #[test]
+    fn test_equal_nodes_are_shared() {
+        let factory = NodeFactory::new();
+
+        let a = factory.make("+", Some(factory.leaf("x")), Some(factory.leaf("y")));
+        let b = factory.make("+", Some(factory.leaf("x")), Some(factory.leaf("y")));
+        let swapped = factory.make("+", Some(factory.leaf("y")), Some(factory.leaf("x")));
+        let left_only = factory.make("+", Some(factory.leaf("x")), None);
+
+        assert!(Rc::ptr_eq(&a, &b));
+        assert!(!Rc::ptr_eq(&a, &swapped));
+        assert!(!Rc::ptr_eq(&a, &left_only));
+        assert_eq!(factory.len(), 5);
+    }
+
+    #[test]
+    fn test_repeated_subexpressions_collapse() {
+        let factory = NodeFactory::new();
+        let root = build_square(&factory);
+
+        assert_eq!(count_nodes(&root), 7);
+        assert_eq!(count_unique_nodes(&root), 4);
+        assert!(Rc::ptr_eq(
+            root.left.as_ref().unwrap(),
+            root.right.as_ref().unwrap()
+        ));
+
+        // Building it again yields the very same tree: O(1) equality
+        assert!(Rc::ptr_eq(&root, &build_square(&factory)));
+    }
+
+    #[test]
+    fn test_unused_nodes_are_freed() {
+        let factory = NodeFactory::new();
+
+        let weak = Rc::downgrade(&build_square(&factory));
+        assert!(weak.upgrade().is_none());
+        assert!(factory.is_empty());
+        assert_eq!(factory.purge(), 4);
+
+        // A kept subtree survives while its dropped parent does not
+        let x = factory.leaf("x");
+        drop(factory.make("-", Some(Rc::clone(&x)), None));
+        assert_eq!(factory.len(), 1);
+        assert_eq!(factory.purge(), 1);
+        assert!(Rc::ptr_eq(&x, &factory.leaf("x")));
+    }
+
+    #[test]
+    fn test_rebuilt_node_after_free() {
+        let factory = NodeFactory::new();
+
+        let first = factory.leaf(1);
+        let weak = Rc::downgrade(&first);
+        drop(first);
+
+        let second = factory.leaf(1);
+        assert!(weak.upgrade().is_none());
+        assert_eq!(second.value, 1);
+        assert_eq!(factory.len(), 1);
+    }
+}
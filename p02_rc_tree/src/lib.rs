@@ -1,3 +1,14 @@
+pub mod bst;
+pub mod diff;
+pub mod factory;
+pub mod map;
+pub mod parallel;
+pub mod sharing;
+pub mod text;
+pub mod traversal;
+pub mod visit;
+pub mod zipper;
+
 use std::rc::Rc;
 
 /// A tree node that holds a value and references to its children.
@@ -41,40 +52,52 @@ impl<T> Node<T> {
             right: Some(right),
         }
     }
-}
-
-pub fn count_nodes<T>(root: &Rc<Node<T>>) -> usize {
-    let left_children_count = match root.left.as_ref() {
-        Some(left) => count_nodes(left),
-        None => 0,
-    };
-
-    let right_children_count = match root.right.as_ref() {
-        Some(right) => count_nodes(right),
-        None => 0,
-    };
 
-    1 + left_children_count + right_children_count
+    pub fn child(&self, side: Side) -> Option<&Rc<Node<T>>> {
+        match side {
+            Side::Left => self.left.as_ref(),
+            Side::Right => self.right.as_ref(),
+        }
+    }
 }
 
-pub fn find_node<T: PartialEq>(root: &Rc<Node<T>>, value: &T) -> Option<Rc<Node<T>>> {
-    if &root.value == value {
-        return Some(Rc::clone(root));
+// Dropping a node normally drops each child `Rc`, which drops its children,
+// and so on: one stack frame per level. Instead, detach the children and
+// free nodes from an explicit stack.
+impl<T> Drop for Node<T> {
+    fn drop(&mut self) {
+        let mut stack: Vec<Rc<Node<T>>> = self
+            .left
+            .take()
+            .into_iter()
+            .chain(self.right.take())
+            .collect();
+
+        while let Some(node) = stack.pop() {
+            // Shared nodes are left to their other owners
+            if let Ok(mut node) = Rc::try_unwrap(node) {
+                stack.extend(node.left.take());
+                stack.extend(node.right.take());
+            }
+        }
     }
+}
 
-    if let Some(left) = root.left.as_ref()
-        && let Some(found) = find_node(left, value)
-    {
-        return Some(found);
-    }
+/// Which child link of a node to follow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    Left,
+    Right,
+}
 
-    if let Some(right) = root.right.as_ref()
-        && let Some(found) = find_node(right, value)
-    {
-        return Some(found);
-    }
+/// Counts nodes without recursion, so degenerate trees cannot overflow the stack.
+pub fn count_nodes<T>(root: &Rc<Node<T>>) -> usize {
+    traversal::pre_order(root).count()
+}
 
-    None
+/// Finds the first node holding `value`, searching root, left, then right.
+pub fn find_node<T: PartialEq>(root: &Rc<Node<T>>, value: &T) -> Option<Rc<Node<T>>> {
+    traversal::pre_order(root).find(|node| &node.value == value)
 }
 
 #[cfg(test)]
@@ -97,6 +120,15 @@ mod tests {
         Rc::new(Node::with_children("A", Rc::clone(&b), Rc::clone(&c)))
     }
 
+    /// Builds a linked-list-shaped tree where every node only has a left child.
+    fn build_deep_tree(depth: usize) -> Rc<Node<usize>> {
+        let mut root = Rc::new(Node::new(0));
+        for value in 1..depth {
+            root = Rc::new(Node::with_left_child(value, root));
+        }
+        root
+    }
+
     #[test]
     fn test_count_nodes() {
         let root = build_test_tree();
@@ -121,6 +153,49 @@ mod tests {
         assert!(found.is_none());
     }
 
+    #[test]
+    fn test_count_nodes_deep_tree() {
+        let root = build_deep_tree(300_000);
+        assert_eq!(count_nodes(&root), 300_000);
+    }
+
+    #[test]
+    fn test_find_node_deep_tree() {
+        let root = build_deep_tree(300_000);
+
+        let found = find_node(&root, &0);
+        assert_eq!(found.map(|node| node.value), Some(0));
+        assert!(find_node(&root, &300_000).is_none());
+    }
+
+    #[test]
+    fn test_traversals_deep_tree() {
+        let root = build_deep_tree(300_000);
+
+        assert_eq!(traversal::in_order(&root).next().unwrap().value, 0);
+        assert_eq!(traversal::post_order(&root).next().unwrap().value, 0);
+        assert_eq!(traversal::level_order(&root).count(), 300_000);
+    }
+
+    #[test]
+    fn test_drop_deep_trees() {
+        drop(build_deep_tree(1_000_000));
+
+        // A right-leaning chain whose lower half is also kept elsewhere
+        let mut lower = Rc::new(Node::new(0));
+        for value in 1..500_000 {
+            lower = Rc::new(Node::with_right_child(value, lower));
+        }
+        let mut upper = Rc::clone(&lower);
+        for value in 500_000..1_000_000 {
+            upper = Rc::new(Node::with_right_child(value, upper));
+        }
+
+        drop(upper);
+        assert_eq!(Rc::strong_count(&lower), 1);
+        assert_eq!(count_nodes(&lower), 500_000);
+    }
+
     #[test]
     fn test_parent_pointer_cycle_problem() {
         // This test demonstrates WHY we can't use Rc for parent pointers.
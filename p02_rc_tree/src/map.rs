@@ -0,0 +1,527 @@
+//! Persistent Ordered Map
+//!
+//! An AVL tree built from `Node<MapEntry<K, V>>`. Like the plain persistent
+//! BST, every update copies only the path from the root to the change and
+//! shares all other subtrees with the previous version. Rebalancing keeps
+//! that path O(log n) long even for sorted input.
+
+use std::{
+    cmp::Ordering,
+    ops::{Bound, RangeBounds},
+    rc::Rc,
+};
+
+use crate::Node;
+
+/// A key-value pair stored in a map node, with the cached subtree height.
+#[derive(Debug)]
+pub struct MapEntry<K, V> {
+    pub key: K,
+    pub value: V,
+    height: usize,
+}
+
+impl<K, V> MapEntry<K, V> {
+    /// Height of the subtree rooted at this entry's node (a leaf is 1).
+    pub fn height(&self) -> usize {
+        self.height
+    }
+}
+
+type Link<K, V> = Option<Rc<Node<MapEntry<K, V>>>>;
+
+/// An immutable ordered map. `insert` and `remove` return a new version;
+/// cloning a map is O(1).
+#[derive(Debug)]
+pub struct PersistentMap<K, V> {
+    root: Link<K, V>,
+    len: usize,
+}
+
+impl<K, V> Clone for PersistentMap<K, V> {
+    fn clone(&self) -> Self {
+        PersistentMap {
+            root: self.root.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<K, V> PersistentMap<K, V> {
+    pub fn new() -> Self {
+        PersistentMap { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The root node, for inspecting structure and sharing.
+    pub fn root(&self) -> Option<&Rc<Node<MapEntry<K, V>>>> {
+        self.root.as_ref()
+    }
+}
+
+impl<K, V> Default for PersistentMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> PersistentMap<K, V> {
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let mut curr = self.root.as_ref();
+
+        while let Some(node) = curr {
+            curr = match key.cmp(&node.value.key) {
+                Ordering::Less => node.left.as_ref(),
+                Ordering::Greater => node.right.as_ref(),
+                Ordering::Equal => return Some(&node.value.value),
+            };
+        }
+
+        None
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns a new version with `key` mapped to `value`.
+    pub fn insert(&self, key: K, value: V) -> Self {
+        let (root, added) = insert(self.root.as_ref(), key, value);
+
+        PersistentMap {
+            root: Some(root),
+            len: self.len + usize::from(added),
+        }
+    }
+
+    /// Returns a new version without `key`.
+    /// If `key` is absent, the new version shares the old root.
+    pub fn remove(&self, key: &K) -> Self {
+        match remove(self.root.as_ref(), key) {
+            Some(root) => PersistentMap {
+                root,
+                len: self.len - 1,
+            },
+            None => self.clone(),
+        }
+    }
+
+    /// Entries with keys inside `range`, in ascending key order.
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Range<'_, K, V> {
+        let mut iter = Range {
+            stack: Vec::new(),
+            end: range.end_bound().cloned(),
+        };
+        iter.seek(self.root.as_ref(), range.start_bound());
+        iter
+    }
+
+    /// All entries in ascending key order.
+    pub fn iter(&self) -> Range<'_, K, V> {
+        self.range(..)
+    }
+}
+
+/// Ascending iterator over a key range of a `PersistentMap`.
+pub struct Range<'a, K, V> {
+    stack: Vec<&'a Node<MapEntry<K, V>>>,
+    end: Bound<K>,
+}
+
+impl<'a, K: Ord, V> Range<'a, K, V> {
+    /// Pushes the left spine under `curr`, skipping keys before `start`.
+    fn seek(&mut self, mut curr: Option<&'a Rc<Node<MapEntry<K, V>>>>, start: Bound<&K>) {
+        while let Some(node) = curr {
+            let before_start = match start {
+                Bound::Included(start) => node.value.key < *start,
+                Bound::Excluded(start) => node.value.key <= *start,
+                Bound::Unbounded => false,
+            };
+
+            if before_start {
+                curr = node.right.as_ref();
+            } else {
+                self.stack.push(node);
+                curr = node.left.as_ref();
+            }
+        }
+    }
+}
+
+impl<'a, K: Ord, V> Iterator for Range<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+
+        let past_end = match &self.end {
+            Bound::Included(end) => node.value.key > *end,
+            Bound::Excluded(end) => node.value.key >= *end,
+            Bound::Unbounded => false,
+        };
+        if past_end {
+            self.stack.clear();
+            return None;
+        }
+
+        self.seek(node.right.as_ref(), Bound::Unbounded);
+        Some((&node.value.key, &node.value.value))
+    }
+}
+
+fn height<K, V>(link: Option<&Rc<Node<MapEntry<K, V>>>>) -> usize {
+    link.map_or(0, |node| node.value.height)
+}
+
+fn make<K, V>(key: K, value: V, left: Link<K, V>, right: Link<K, V>) -> Rc<Node<MapEntry<K, V>>> {
+    let height = 1 + height(left.as_ref()).max(height(right.as_ref()));
+
+    Rc::new(Node {
+        value: MapEntry { key, value, height },
+        left,
+        right,
+    })
+}
+
+/// Like `make`, but rotates if the two sides differ in height by two.
+fn balance<K: Clone, V: Clone>(
+    key: K,
+    value: V,
+    left: Link<K, V>,
+    right: Link<K, V>,
+) -> Rc<Node<MapEntry<K, V>>> {
+    let left_height = height(left.as_ref());
+    let right_height = height(right.as_ref());
+
+    if left_height > right_height + 1 {
+        let l = left.expect("taller side is not empty");
+        let (l_entry, l_left, l_right) = (&l.value, &l.left, &l.right);
+
+        if height(l_left.as_ref()) >= height(l_right.as_ref()) {
+            // Single right rotation
+            let new_right = make(key, value, l_right.clone(), right);
+            make(
+                l_entry.key.clone(),
+                l_entry.value.clone(),
+                l_left.clone(),
+                Some(new_right),
+            )
+        } else {
+            // Left-right double rotation
+            let lr = l_right.as_ref().expect("taller inner side is not empty");
+            let new_left = make(
+                l_entry.key.clone(),
+                l_entry.value.clone(),
+                l_left.clone(),
+                lr.left.clone(),
+            );
+            let new_right = make(key, value, lr.right.clone(), right);
+            make(
+                lr.value.key.clone(),
+                lr.value.value.clone(),
+                Some(new_left),
+                Some(new_right),
+            )
+        }
+    } else if right_height > left_height + 1 {
+        let r = right.expect("taller side is not empty");
+        let (r_entry, r_left, r_right) = (&r.value, &r.left, &r.right);
+
+        if height(r_right.as_ref()) >= height(r_left.as_ref()) {
+            // Single left rotation
+            let new_left = make(key, value, left, r_left.clone());
+            make(
+                r_entry.key.clone(),
+                r_entry.value.clone(),
+                Some(new_left),
+                r_right.clone(),
+            )
+        } else {
+            // Right-left double rotation
+            let rl = r_left.as_ref().expect("taller inner side is not empty");
+            let new_left = make(key, value, left, rl.left.clone());
+            let new_right = make(
+                r_entry.key.clone(),
+                r_entry.value.clone(),
+                rl.right.clone(),
+                r_right.clone(),
+            );
+            make(
+                rl.value.key.clone(),
+                rl.value.value.clone(),
+                Some(new_left),
+                Some(new_right),
+            )
+        }
+    } else {
+        make(key, value, left, right)
+    }
+}
+
+/// Returns the new subtree root and whether a new key was added.
+fn insert<K: Ord + Clone, V: Clone>(
+    link: Option<&Rc<Node<MapEntry<K, V>>>>,
+    key: K,
+    value: V,
+) -> (Rc<Node<MapEntry<K, V>>>, bool) {
+    let Some(node) = link else {
+        return (make(key, value, None, None), true);
+    };
+    let entry = &node.value;
+
+    match key.cmp(&entry.key) {
+        Ordering::Less => {
+            let (left, added) = insert(node.left.as_ref(), key, value);
+            let root = balance(
+                entry.key.clone(),
+                entry.value.clone(),
+                Some(left),
+                node.right.clone(),
+            );
+            (root, added)
+        }
+        Ordering::Greater => {
+            let (right, added) = insert(node.right.as_ref(), key, value);
+            let root = balance(
+                entry.key.clone(),
+                entry.value.clone(),
+                node.left.clone(),
+                Some(right),
+            );
+            (root, added)
+        }
+        Ordering::Equal => (
+            make(key, value, node.left.clone(), node.right.clone()),
+            false,
+        ),
+    }
+}
+
+/// Returns the new subtree root, or `None` if `key` was not found.
+fn remove<K: Ord + Clone, V: Clone>(
+    link: Option<&Rc<Node<MapEntry<K, V>>>>,
+    key: &K,
+) -> Option<Link<K, V>> {
+    let node = link?;
+    let entry = &node.value;
+
+    let root = match key.cmp(&entry.key) {
+        Ordering::Less => {
+            let left = remove(node.left.as_ref(), key)?;
+            balance(
+                entry.key.clone(),
+                entry.value.clone(),
+                left,
+                node.right.clone(),
+            )
+        }
+        Ordering::Greater => {
+            let right = remove(node.right.as_ref(), key)?;
+            balance(
+                entry.key.clone(),
+                entry.value.clone(),
+                node.left.clone(),
+                right,
+            )
+        }
+        Ordering::Equal => match (&node.left, &node.right) {
+            (None, right) => return Some(right.clone()),
+            (left, None) => return Some(left.clone()),
+            (Some(left), Some(right)) => {
+                let (min_key, min_value, right) = remove_min(right);
+                balance(min_key, min_value, Some(Rc::clone(left)), right)
+            }
+        },
+    };
+
+    Some(Some(root))
+}
+
+/// Removes the smallest entry, returning it and the remaining subtree.
+fn remove_min<K: Clone, V: Clone>(node: &Rc<Node<MapEntry<K, V>>>) -> (K, V, Link<K, V>) {
+    let entry = &node.value;
+
+    match node.left.as_ref() {
+        None => (entry.key.clone(), entry.value.clone(), node.right.clone()),
+        Some(left) => {
+            let (min_key, min_value, left) = remove_min(left);
+            let root = balance(
+                entry.key.clone(),
+                entry.value.clone(),
+                left,
+                node.right.clone(),
+            );
+            (min_key, min_value, Some(root))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, ptr};
+
+    use super::*;
+    use crate::traversal;
+
+    /// Small xorshift generator so the property tests need no dependencies.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, bound: usize) -> usize {
+            (self.next() % bound as u64) as usize
+        }
+    }
+
+    /// Checks ordering, cached heights and AVL balance.
+    /// Returns (height, size) of the subtree.
+    fn check_invariants<K: Ord, V>(
+        link: Option<&Rc<Node<MapEntry<K, V>>>>,
+        lower: Option<&K>,
+        upper: Option<&K>,
+    ) -> (usize, usize) {
+        let Some(node) = link else {
+            return (0, 0);
+        };
+        let key = &node.value.key;
+
+        assert!(lower.is_none_or(|lower| lower < key), "keys out of order");
+        assert!(upper.is_none_or(|upper| key < upper), "keys out of order");
+
+        let (left_height, left_size) = check_invariants(node.left.as_ref(), lower, Some(key));
+        let (right_height, right_size) = check_invariants(node.right.as_ref(), Some(key), upper);
+
+        assert!(left_height.abs_diff(right_height) <= 1, "unbalanced node");
+        let height = 1 + left_height.max(right_height);
+        assert_eq!(node.value.height(), height, "stale cached height");
+
+        (height, 1 + left_size + right_size)
+    }
+
+    fn assert_matches_model(map: &PersistentMap<usize, u64>, model: &BTreeMap<usize, u64>) {
+        let (_, size) = check_invariants(map.root(), None, None);
+        assert_eq!(size, map.len());
+        assert_eq!(map.len(), model.len());
+        assert!(map.iter().eq(model.iter()));
+    }
+
+    #[test]
+    fn test_insert_get_remove() {
+        let empty = PersistentMap::new();
+        let v1 = empty.insert("b", 2).insert("a", 1).insert("c", 3);
+        let v2 = v1.insert("b", 20);
+        let v3 = v2.remove(&"a");
+
+        assert!(empty.is_empty());
+        assert_eq!(v1.len(), 3);
+        assert_eq!(v1.get(&"b"), Some(&2));
+        assert_eq!(v2.get(&"b"), Some(&20));
+        assert_eq!(v2.len(), 3);
+        assert!(v1.contains_key(&"a"));
+        assert!(!v3.contains_key(&"a"));
+        assert_eq!(v3.len(), 2);
+        assert_eq!(v3.get(&"z"), None);
+    }
+
+    #[test]
+    fn test_range_iteration() {
+        let map = (0..20).fold(PersistentMap::new(), |map, key| map.insert(key, key * 10));
+
+        let keys = |iter: Range<'_, i32, i32>| iter.map(|(key, _)| *key).collect::<Vec<_>>();
+
+        assert_eq!(keys(map.range(5..9)), [5, 6, 7, 8]);
+        assert_eq!(keys(map.range(17..)), [17, 18, 19]);
+        assert_eq!(keys(map.range(..=2)), [0, 1, 2]);
+        assert_eq!(
+            keys(map.range((Bound::Excluded(3), Bound::Included(5)))),
+            [4, 5]
+        );
+        assert!(keys(map.range(30..)).is_empty());
+        assert_eq!(
+            map.range(10..12).collect::<Vec<_>>(),
+            [(&10, &100), (&11, &110)]
+        );
+    }
+
+    #[test]
+    fn test_sorted_input_stays_balanced() {
+        let map = (0..10_000).fold(PersistentMap::new(), |map, key| map.insert(key, ()));
+
+        let (height, size) = check_invariants(map.root(), None, None);
+        assert_eq!(size, 10_000);
+        // An AVL tree is at most ~1.44 * log2(n) high
+        assert!(height <= 20, "height {} is too large", height);
+    }
+
+    #[test]
+    fn test_versions_share_structure() {
+        let v1 = (0..1_000).fold(PersistentMap::new(), |map, key| map.insert(key, key));
+        let v2 = v1.insert(500, 0);
+
+        let old_nodes: Vec<*const Node<MapEntry<i32, i32>>> =
+            traversal::pre_order(v1.root().unwrap())
+                .map(|node| Rc::as_ptr(&node))
+                .collect();
+        let copied = traversal::pre_order(v2.root().unwrap())
+            .filter(|node| !old_nodes.iter().any(|old| ptr::eq(*old, Rc::as_ptr(node))))
+            .count();
+
+        // Only the root-to-key path is new
+        assert!(copied <= v1.root().unwrap().value.height());
+        assert_eq!(v1.get(&500), Some(&500));
+        assert_eq!(v2.get(&500), Some(&0));
+
+        // Removing a missing key reuses the whole tree
+        let v3 = v2.remove(&5_000);
+        assert!(Rc::ptr_eq(v2.root().unwrap(), v3.root().unwrap()));
+    }
+
+    #[test]
+    fn test_random_operations_keep_invariants() {
+        let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+
+        for _ in 0..20 {
+            let mut map = PersistentMap::new();
+            let mut model = BTreeMap::new();
+            let mut history = Vec::new();
+
+            for step in 0..400 {
+                let key = rng.below(200);
+                if rng.below(3) == 0 {
+                    map = map.remove(&key);
+                    model.remove(&key);
+                } else {
+                    map = map.insert(key, step);
+                    model.insert(key, step);
+                }
+
+                assert_matches_model(&map, &model);
+                if step % 50 == 0 {
+                    history.push((map.clone(), model.clone()));
+                }
+            }
+
+            // Every saved version is still intact
+            for (map, model) in &history {
+                assert_matches_model(map, model);
+            }
+
+            let start = rng.below(200);
+            let end = start + rng.below(50);
+            assert!(map.range(start..end).eq(model.range(start..end)));
+        }
+    }
+}
@@ -0,0 +1,506 @@
+//! Parallel Tree Algorithms
+//!
+//! `Node<T>` uses `Rc`, which is not `Send`, so its trees cannot be shared
+//! with other threads. `ArcNode<T>` is the same shape built on `Arc`.
+//! The algorithms here use fork-join recursion over `std::thread::scope`:
+//! a node forks only when both of its subtrees have at least `cutoff`
+//! nodes, and smaller subtrees are processed sequentially on the current
+//! thread. Forks nest at most a few levels deep (see `fork_depth`), so the
+//! tree's shape bounds neither the recursion nor the number of threads.
+
+use std::{
+    collections::HashMap,
+    num::NonZeroUsize,
+    rc::Rc,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+};
+
+use crate::{Node, Side};
+
+/// A tree node like `Node<T>`, but shareable across threads.
+///
+/// The children are fixed at construction, so the cached size stays right.
+#[derive(Debug)]
+pub struct ArcNode<T> {
+    pub value: T,
+    left: Option<Arc<ArcNode<T>>>,
+    right: Option<Arc<ArcNode<T>>>,
+    size: usize,
+}
+
+impl<T> ArcNode<T> {
+    pub fn new(value: T) -> Self {
+        Self::with_children(value, None, None)
+    }
+
+    pub fn with_children(
+        value: T,
+        left: Option<Arc<ArcNode<T>>>,
+        right: Option<Arc<ArcNode<T>>>,
+    ) -> Self {
+        let size = [&left, &right]
+            .into_iter()
+            .flatten()
+            .map(|child| child.size)
+            .fold(1_usize, usize::saturating_add);
+
+        ArcNode {
+            value,
+            left,
+            right,
+            size,
+        }
+    }
+
+    pub fn left(&self) -> Option<&Arc<ArcNode<T>>> {
+        self.left.as_ref()
+    }
+
+    pub fn right(&self) -> Option<&Arc<ArcNode<T>>> {
+        self.right.as_ref()
+    }
+
+    /// Nodes in this subtree, cached at construction for the fork cutoff.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Copies an `Rc` tree. Subtrees shared in the source stay shared.
+    pub fn from_rc(root: &Rc<Node<T>>) -> Arc<ArcNode<T>>
+    where
+        T: Clone,
+    {
+        let mut converted: HashMap<*const Node<T>, Arc<ArcNode<T>>> = HashMap::new();
+        let mut stack = vec![(Rc::clone(root), false)];
+
+        while let Some((node, expanded)) = stack.pop() {
+            let ptr = Rc::as_ptr(&node);
+            if converted.contains_key(&ptr) {
+                continue;
+            }
+
+            if expanded {
+                let convert = |child: &Option<Rc<Node<T>>>| {
+                    child
+                        .as_ref()
+                        .map(|child| Arc::clone(&converted[&Rc::as_ptr(child)]))
+                };
+                let arc_node = ArcNode::with_children(
+                    node.value.clone(),
+                    convert(&node.left),
+                    convert(&node.right),
+                );
+                converted.insert(ptr, Arc::new(arc_node));
+                continue;
+            }
+
+            let children: Vec<Rc<Node<T>>> = [&node.right, &node.left]
+                .into_iter()
+                .flatten()
+                .cloned()
+                .collect();
+            stack.push((node, true));
+            stack.extend(children.into_iter().map(|child| (child, false)));
+        }
+
+        converted
+            .remove(&Rc::as_ptr(root))
+            .expect("root was converted")
+    }
+}
+
+// Same as for `Node`: free deep trees from an explicit stack.
+impl<T> Drop for ArcNode<T> {
+    fn drop(&mut self) {
+        let mut stack: Vec<Arc<ArcNode<T>>> = self
+            .left
+            .take()
+            .into_iter()
+            .chain(self.right.take())
+            .collect();
+
+        while let Some(node) = stack.pop() {
+            if let Ok(mut node) = Arc::try_unwrap(node) {
+                stack.extend(node.left.take());
+                stack.extend(node.right.take());
+            }
+        }
+    }
+}
+
+/// Folds the tree bottom-up in parallel.
+///
+/// `combine` receives a node's value and the results of its left and right
+/// subtrees (`None` for a missing child).
+pub fn par_fold<T, R, F>(root: &Arc<ArcNode<T>>, cutoff: usize, combine: &F) -> R
+where
+    T: Send + Sync,
+    R: Send,
+    F: Fn(&T, Option<R>, Option<R>) -> R + Sync,
+{
+    let combine_node = |node: &Arc<ArcNode<T>>, left, right| combine(&node.value, left, right);
+
+    fold_nodes(root, cutoff, fork_depth(), &combine_node)
+}
+
+/// Counts nodes in parallel, like `count_nodes`.
+pub fn par_count_nodes<T: Send + Sync>(root: &Arc<ArcNode<T>>, cutoff: usize) -> usize {
+    par_fold(
+        root,
+        cutoff,
+        &|_, left: Option<usize>, right: Option<usize>| 1 + left.unwrap_or(0) + right.unwrap_or(0),
+    )
+}
+
+/// Finds a node holding `value` in parallel. Like `find_node`, the first
+/// match in pre-order wins, and the search stops as soon as it is known.
+pub fn par_find_node<T>(root: &Arc<ArcNode<T>>, value: &T, cutoff: usize) -> Option<Arc<ArcNode<T>>>
+where
+    T: PartialEq + Send + Sync,
+{
+    let search = Search {
+        value,
+        cutoff,
+        best: AtomicUsize::new(usize::MAX),
+    };
+
+    search
+        .find(root, 0, fork_depth())
+        .map(|(_, node)| Arc::clone(node))
+}
+
+/// How many levels of forks to allow: enough to keep every core busy,
+/// while bounding both the recursion depth and the number of threads.
+fn fork_depth() -> u32 {
+    let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    (threads * 4).ilog2()
+}
+
+/// Shared state of one `par_find_node` call.
+struct Search<'a, T> {
+    value: &'a T,
+    cutoff: usize,
+    /// Pre-order index of the earliest match found so far.
+    best: AtomicUsize,
+}
+
+impl<T: PartialEq + Send + Sync> Search<'_, T> {
+    /// Pre-order search of the subtree at pre-order index `index`. Gives up
+    /// once any thread has found a match before the node it is looking at.
+    fn find<'n>(
+        &self,
+        root: &'n Arc<ArcNode<T>>,
+        index: usize,
+        forks_left: u32,
+    ) -> Option<(usize, &'n Arc<ArcNode<T>>)> {
+        let mut stack = vec![(root, index)];
+
+        while let Some((node, index)) = stack.pop() {
+            // The stack pops in pre-order, so nothing left here can win
+            if self.best.load(Ordering::Relaxed) < index {
+                return None;
+            }
+            if node.value == *self.value {
+                self.best.fetch_min(index, Ordering::Relaxed);
+                return Some((index, node));
+            }
+
+            let right_index = index + 1 + node.left.as_ref().map_or(0, |left| left.size);
+            match (&node.left, &node.right) {
+                (Some(left), Some(right))
+                    if forks_left > 0 && left.size >= self.cutoff && right.size >= self.cutoff =>
+                {
+                    let (left, right) = thread::scope(|scope| {
+                        let handle = scope.spawn(|| self.find(right, right_index, forks_left - 1));
+                        let left = self.find(left, index + 1, forks_left - 1);
+                        (left, handle.join().expect("find thread panicked"))
+                    });
+                    if let Some(found) = left.or(right) {
+                        return Some(found);
+                    }
+                }
+                (left, right) => {
+                    stack.extend(right.iter().map(|right| (right, right_index)));
+                    stack.extend(left.iter().map(|left| (left, index + 1)));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Fork-join fold over nodes.
+///
+/// Walks down while only one child is large enough to fork, then either
+/// forks (both children large) or finishes sequentially, and folds the
+/// walked spine back up. Forks recurse at most `forks_left` levels deep;
+/// below that, subtrees are folded sequentially.
+fn fold_nodes<T, R, F>(root: &Arc<ArcNode<T>>, cutoff: usize, forks_left: u32, combine: &F) -> R
+where
+    T: Send + Sync,
+    R: Send,
+    F: Fn(&Arc<ArcNode<T>>, Option<R>, Option<R>) -> R + Sync,
+{
+    fn large<T>(child: &Option<Arc<ArcNode<T>>>, cutoff: usize) -> Option<&Arc<ArcNode<T>>> {
+        child.as_ref().filter(|child| child.size >= cutoff)
+    }
+
+    let mut spine = Vec::new();
+    let mut curr = root;
+
+    let bottom = loop {
+        match (large(&curr.left, cutoff), large(&curr.right, cutoff)) {
+            (Some(_), Some(_)) if forks_left == 0 => break fold_sequential(curr, combine),
+            (Some(left), Some(right)) => {
+                let (left, right) = thread::scope(|scope| {
+                    let handle = scope.spawn(|| fold_nodes(left, cutoff, forks_left - 1, combine));
+                    let right = fold_nodes(right, cutoff, forks_left - 1, combine);
+                    (handle.join().expect("fold thread panicked"), right)
+                });
+                break combine(curr, Some(left), Some(right));
+            }
+            (Some(left), None) => {
+                spine.push((curr, Side::Left));
+                curr = left;
+            }
+            (None, Some(right)) => {
+                spine.push((curr, Side::Right));
+                curr = right;
+            }
+            (None, None) => break fold_sequential(curr, combine),
+        }
+    };
+
+    spine.into_iter().rev().fold(bottom, |below, (node, side)| {
+        let other = |child: &Option<Arc<ArcNode<T>>>| {
+            child.as_ref().map(|child| fold_sequential(child, combine))
+        };
+        match side {
+            Side::Left => combine(node, Some(below), other(&node.right)),
+            Side::Right => combine(node, other(&node.left), Some(below)),
+        }
+    })
+}
+
+/// Post-order fold on the current thread, using an explicit stack.
+fn fold_sequential<T, R, F>(root: &Arc<ArcNode<T>>, combine: &F) -> R
+where
+    F: Fn(&Arc<ArcNode<T>>, Option<R>, Option<R>) -> R,
+{
+    let mut stack = vec![(root, false)];
+    let mut results: Vec<R> = Vec::new();
+
+    while let Some((node, expanded)) = stack.pop() {
+        if expanded {
+            // The left result was pushed first, so the right one is on top
+            let right = node
+                .right
+                .as_ref()
+                .map(|_| results.pop().expect("right result"));
+            let left = node
+                .left
+                .as_ref()
+                .map(|_| results.pop().expect("left result"));
+            results.push(combine(node, left, right));
+            continue;
+        }
+
+        stack.push((node, true));
+        stack.extend(node.right.iter().map(|right| (right, false)));
+        stack.extend(node.left.iter().map(|left| (left, false)));
+    }
+
+    results.pop().expect("root result")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+    use crate::{count_nodes, find_node};
+
+    /// Builds a complete tree of the given depth with values 1, 2, 3, ... in pre-order.
+    fn build_rc_tree(depth: u32) -> Rc<Node<u64>> {
+        fn build(depth: u32, next: &mut u64) -> Rc<Node<u64>> {
+            *next += 1;
+            let value = *next;
+            if depth == 1 {
+                return Rc::new(Node::new(value));
+            }
+            let left = build(depth - 1, next);
+            let right = build(depth - 1, next);
+            Rc::new(Node::with_children(value, left, right))
+        }
+
+        build(depth, &mut 0)
+    }
+
+    /// Times the sequential `Rc` versions of count and find.
+    fn benchmark_sequential(depth: u32) -> u128 {
+        let root = build_rc_tree(depth);
+        let last = (1_u64 << depth) - 1;
+        // The last node of the left half, so a search can stop halfway
+        let middle = 1_u64 << (depth - 1);
+
+        let start = Instant::now();
+        assert_eq!(count_nodes(&root) as u64, last);
+        assert!(find_node(&root, &last).is_some());
+        assert!(find_node(&root, &middle).is_some());
+
+        start.elapsed().as_millis()
+    }
+
+    /// Times the parallel `Arc` versions of count and find.
+    fn benchmark_parallel(depth: u32, cutoff: usize) -> u128 {
+        let root = ArcNode::from_rc(&build_rc_tree(depth));
+        let last = (1_u64 << depth) - 1;
+        // The last node of the left half, so a search can stop halfway
+        let middle = 1_u64 << (depth - 1);
+
+        let start = Instant::now();
+        assert_eq!(par_count_nodes(&root, cutoff) as u64, last);
+        assert!(par_find_node(&root, &last, cutoff).is_some());
+        assert!(par_find_node(&root, &middle, cutoff).is_some());
+
+        start.elapsed().as_millis()
+    }
+
+    #[test]
+    fn test_from_rc_preserves_shape_and_sharing() {
+        let shared = Rc::new(Node::new(4));
+        let left = Rc::new(Node::with_left_child(2, Rc::clone(&shared)));
+        let right = Rc::new(Node::with_right_child(3, shared));
+        let root = ArcNode::from_rc(&Rc::new(Node::with_children(1, left, right)));
+
+        assert_eq!(root.value, 1);
+        assert_eq!(root.size(), 5);
+
+        let (left, right) = (root.left().unwrap(), root.right().unwrap());
+        assert_eq!((left.value, right.value), (2, 3));
+        assert!(Arc::ptr_eq(left.left().unwrap(), right.right().unwrap()));
+    }
+
+    #[test]
+    fn test_parallel_matches_sequential() {
+        let rc_root = build_rc_tree(12);
+        let root = ArcNode::from_rc(&rc_root);
+
+        for cutoff in [1, 64, 10_000] {
+            assert_eq!(par_count_nodes(&root, cutoff), count_nodes(&rc_root));
+
+            let found = par_find_node(&root, &4_000, cutoff).unwrap();
+            assert_eq!(found.value, find_node(&rc_root, &4_000).unwrap().value);
+            assert!(par_find_node(&root, &0, cutoff).is_none());
+
+            let sum = par_fold(&root, cutoff, &|value: &u64, left, right| {
+                value + left.unwrap_or(0) + right.unwrap_or(0)
+            });
+            assert_eq!(sum, (1..=4_095).sum::<u64>());
+        }
+    }
+
+    #[test]
+    fn test_find_returns_first_in_pre_order() {
+        let root = Arc::new(ArcNode::with_children(
+            0,
+            Some(Arc::new(ArcNode::with_children(
+                1,
+                Some(Arc::new(ArcNode::new(7))),
+                None,
+            ))),
+            Some(Arc::new(ArcNode::new(7))),
+        ));
+        let found = par_find_node(&root, &7, 1).unwrap();
+
+        let left = root.left().unwrap();
+        assert!(Arc::ptr_eq(&found, left.left().unwrap()));
+    }
+
+    #[test]
+    fn test_deep_chain_does_not_overflow() {
+        let mut root = Arc::new(ArcNode::new(0_u64));
+        for value in 1..200_000 {
+            root = Arc::new(ArcNode::with_children(value, Some(root), None));
+        }
+
+        assert_eq!(par_count_nodes(&root, 1_000), 200_000);
+        assert_eq!(par_find_node(&root, &0, 1_000).unwrap().value, 0);
+    }
+
+    #[test]
+    fn test_caterpillar_with_small_cutoff() {
+        // Every spine node has a leaf on the other side, so with cutoff 1
+        // every level could fork
+        for spine_on_left in [false, true] {
+            let mut root = Arc::new(ArcNode::new(0_u64));
+            for value in 1..50_000 {
+                let leaf = Some(Arc::new(ArcNode::new(0)));
+                root = Arc::new(if spine_on_left {
+                    ArcNode::with_children(value, Some(root), leaf)
+                } else {
+                    ArcNode::with_children(value, leaf, Some(root))
+                });
+            }
+
+            assert_eq!(par_count_nodes(&root, 1), 99_999);
+            let sum = par_fold(&root, 1, &|value: &u64, left, right| {
+                value + left.unwrap_or(0) + right.unwrap_or(0)
+            });
+            assert_eq!(sum, (1..50_000).sum::<u64>());
+            assert_eq!(par_find_node(&root, &1, 1).unwrap().value, 1);
+        }
+    }
+
+    #[test]
+    fn test_find_stops_after_first_match() {
+        static COMPARISONS: AtomicUsize = AtomicUsize::new(0);
+
+        struct Counted(u32);
+
+        impl PartialEq for Counted {
+            fn eq(&self, other: &Self) -> bool {
+                COMPARISONS.fetch_add(1, Ordering::Relaxed);
+                self.0 == other.0
+            }
+        }
+
+        fn complete(depth: u32, next: &mut u32) -> Arc<ArcNode<Counted>> {
+            *next += 1;
+            let node = Counted(*next);
+            if depth == 1 {
+                return Arc::new(ArcNode::new(node));
+            }
+            let left = complete(depth - 1, next);
+            let right = complete(depth - 1, next);
+            Arc::new(ArcNode::with_children(node, Some(left), Some(right)))
+        }
+
+        // 65,535 nodes; the root's left child holds 2
+        let root = complete(16, &mut 0);
+
+        par_find_node(&root, &Counted(2), usize::MAX).unwrap();
+        assert_eq!(COMPARISONS.swap(0, Ordering::Relaxed), 2);
+
+        // The right half may be searched while the left finds the match,
+        // but nothing more
+        let found = par_find_node(&root, &Counted(2), 1).unwrap();
+        assert_eq!(found.value.0, 2);
+        assert!(COMPARISONS.load(Ordering::Relaxed) <= 2 + 32_767);
+    }
+
+    #[test]
+    #[ignore = "benchmark; run with `--ignored --nocapture`"]
+    fn test_benchmark_parallel_vs_sequential() {
+        let sequential = benchmark_sequential(18);
+        let parallel = benchmark_parallel(18, 1 << 14);
+
+        println!("Sequential: {}ms", sequential);
+        println!("Parallel:   {}ms", parallel);
+    }
+}
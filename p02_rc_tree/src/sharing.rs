@@ -0,0 +1,248 @@
+//! Shared-Subtree Analysis
+//!
+//! Because children are `Rc`, one subtree can hang off several parents,
+//! turning the tree into a DAG. `count_nodes` then counts the shared
+//! subtree once per parent. These utilities count each allocation once,
+//! using pointer identity, and report how much memory sharing saves.
+
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use crate::Node;
+
+/// A subtree reachable through more than one parent link.
+#[derive(Debug)]
+pub struct SharedSubtree<T> {
+    pub node: Rc<Node<T>>,
+    /// Number of `left`/`right` links inside the graph pointing at this node.
+    pub parents: usize,
+    /// Nodes in this subtree as `count_nodes` would count them.
+    pub logical_size: usize,
+}
+
+/// Physical vs logical size of a tree with shared subtrees.
+#[derive(Debug)]
+pub struct SharingReport<T> {
+    /// Distinct node allocations.
+    pub physical_nodes: usize,
+    /// Nodes counted once per path from the root, like `count_nodes`.
+    pub logical_nodes: usize,
+    /// Shared subtrees, most parents first, then largest first.
+    pub most_shared: Vec<SharedSubtree<T>>,
+}
+
+impl<T> SharingReport<T> {
+    /// Node allocations avoided thanks to sharing.
+    pub fn saved_nodes(&self) -> usize {
+        self.logical_nodes - self.physical_nodes
+    }
+}
+
+/// Counts distinct node allocations, visiting each shared subtree once.
+pub fn count_unique_nodes<T>(root: &Rc<Node<T>>) -> usize {
+    let mut seen: HashSet<*const Node<T>> = HashSet::new();
+    let mut stack = vec![Rc::clone(root)];
+
+    while let Some(node) = stack.pop() {
+        if !seen.insert(Rc::as_ptr(&node)) {
+            continue;
+        }
+        stack.extend(node.left.iter().cloned());
+        stack.extend(node.right.iter().cloned());
+    }
+
+    seen.len()
+}
+
+/// Every subtree with more than one parent, most-shared first.
+pub fn shared_subtrees<T>(root: &Rc<Node<T>>) -> Vec<SharedSubtree<T>> {
+    collect_shared(analyze(root))
+}
+
+/// Summarizes sharing under `root`, listing at most `top` shared subtrees.
+pub fn sharing_report<T>(root: &Rc<Node<T>>, top: usize) -> SharingReport<T> {
+    let analysis = analyze(root);
+    let physical_nodes = analysis.nodes.len();
+    let logical_nodes = analysis.sizes[&Rc::as_ptr(root)];
+
+    let mut most_shared = collect_shared(analysis);
+    most_shared.truncate(top);
+
+    SharingReport {
+        physical_nodes,
+        logical_nodes,
+        most_shared,
+    }
+}
+
+struct Analysis<T> {
+    /// Distinct nodes in post-order.
+    nodes: Vec<Rc<Node<T>>>,
+    parents: HashMap<*const Node<T>, usize>,
+    sizes: HashMap<*const Node<T>, usize>,
+}
+
+fn collect_shared<T>(analysis: Analysis<T>) -> Vec<SharedSubtree<T>> {
+    let mut shared: Vec<SharedSubtree<T>> = analysis
+        .nodes
+        .into_iter()
+        .filter_map(|node| {
+            let ptr = Rc::as_ptr(&node);
+            let parents = analysis.parents.get(&ptr).copied().unwrap_or(0);
+            (parents > 1).then(|| SharedSubtree {
+                logical_size: analysis.sizes[&ptr],
+                node,
+                parents,
+            })
+        })
+        .collect();
+
+    shared.sort_by(|a, b| {
+        b.parents
+            .cmp(&a.parents)
+            .then(b.logical_size.cmp(&a.logical_size))
+    });
+    shared
+}
+
+/// One post-order pass over distinct nodes, collecting parent-link counts
+/// and memoized logical subtree sizes.
+fn analyze<T>(root: &Rc<Node<T>>) -> Analysis<T> {
+    let mut analysis = Analysis {
+        nodes: Vec::new(),
+        parents: HashMap::new(),
+        sizes: HashMap::new(),
+    };
+    let mut seen: HashSet<*const Node<T>> = HashSet::new();
+    let mut stack = vec![(Rc::clone(root), false)];
+
+    while let Some((node, expanded)) = stack.pop() {
+        let ptr = Rc::as_ptr(&node);
+
+        if expanded {
+            // Children were expanded before us, so their sizes are known
+            let size = [&node.left, &node.right]
+                .into_iter()
+                .flatten()
+                .map(|child| analysis.sizes[&Rc::as_ptr(child)])
+                .fold(1_usize, usize::saturating_add);
+            analysis.sizes.insert(ptr, size);
+            analysis.nodes.push(node);
+            continue;
+        }
+
+        if !seen.insert(ptr) {
+            continue;
+        }
+
+        let children: Vec<Rc<Node<T>>> = [&node.right, &node.left]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect();
+
+        stack.push((node, true));
+        for child in children {
+            *analysis.parents.entry(Rc::as_ptr(&child)).or_insert(0) += 1;
+            stack.push((child, false));
+        }
+    }
+
+    analysis
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::count_nodes;
+
+    /// Builds this DAG, where S is shared by B and C:
+    ///        A
+    ///       / \
+    ///      B   C
+    ///     / \ /
+    ///    D   S
+    ///       / \
+    ///      X   Y
+    fn build_shared_tree() -> (Rc<Node<&'static str>>, Rc<Node<&'static str>>) {
+        let x = Rc::new(Node::new("X"));
+        let y = Rc::new(Node::new("Y"));
+        let s = Rc::new(Node::with_children("S", x, y));
+        let d = Rc::new(Node::new("D"));
+
+        let b = Rc::new(Node::with_children("B", d, Rc::clone(&s)));
+        let c = Rc::new(Node::with_left_child("C", Rc::clone(&s)));
+
+        (Rc::new(Node::with_children("A", b, c)), s)
+    }
+
+    #[test]
+    fn test_count_unique_nodes() {
+        let (root, _) = build_shared_tree();
+
+        assert_eq!(count_nodes(&root), 10);
+        assert_eq!(count_unique_nodes(&root), 7);
+    }
+
+    #[test]
+    fn test_shared_subtrees() {
+        let (root, s) = build_shared_tree();
+
+        let shared = shared_subtrees(&root);
+        assert_eq!(shared.len(), 1);
+        assert!(Rc::ptr_eq(&shared[0].node, &s));
+        assert_eq!(shared[0].parents, 2);
+        assert_eq!(shared[0].logical_size, 3);
+    }
+
+    #[test]
+    fn test_plain_tree_has_no_sharing() {
+        let leaf = Rc::new(Node::new(1));
+        let root = Rc::new(Node::with_left_child(0, leaf));
+
+        let report = sharing_report(&root, 5);
+        assert_eq!(report.physical_nodes, 2);
+        assert_eq!(report.logical_nodes, 2);
+        assert_eq!(report.saved_nodes(), 0);
+        assert!(report.most_shared.is_empty());
+    }
+
+    #[test]
+    fn test_report_on_doubling_chain() {
+        // Each level points at the level below twice:
+        // 31 nodes logically, but only 5 allocations
+        let mut node = Rc::new(Node::new(0));
+        for level in 1..5 {
+            node = Rc::new(Node::with_children(level, Rc::clone(&node), node));
+        }
+
+        let report = sharing_report(&node, 2);
+        assert_eq!(report.physical_nodes, 5);
+        assert_eq!(report.logical_nodes, count_nodes(&node));
+        assert_eq!(report.logical_nodes, 31);
+        assert_eq!(report.saved_nodes(), 26);
+
+        let most_shared: Vec<(i32, usize, usize)> = report
+            .most_shared
+            .iter()
+            .map(|shared| (shared.node.value, shared.parents, shared.logical_size))
+            .collect();
+        assert_eq!(most_shared, [(3, 2, 15), (2, 2, 7)]);
+    }
+
+    #[test]
+    fn test_report_on_huge_logical_tree() {
+        // 2^70 - 1 logical nodes: sizes saturate instead of overflowing
+        let mut node = Rc::new(Node::new(0));
+        for level in 1..70 {
+            node = Rc::new(Node::with_children(level, Rc::clone(&node), node));
+        }
+
+        let report = sharing_report(&node, 1);
+        assert_eq!(report.physical_nodes, 70);
+        assert_eq!(report.logical_nodes, usize::MAX);
+        assert_eq!(report.most_shared[0].logical_size, usize::MAX);
+    }
+}
@@ -0,0 +1,406 @@
+//! Text Format and Pretty-Printing
+//!
+//! Trees written as compact S-expressions: `(A (B D _) (C _ E))`.
+//! A list is `(value left right)`, a bare atom is a leaf, and `_` is a
+//! missing child. Parsing and rendering both use explicit stacks, so
+//! deeply nested input cannot overflow the call stack.
+
+use std::{error::Error, fmt, rc::Rc, str::FromStr};
+
+use crate::Node;
+
+/// Atom that stands for a missing child.
+pub const EMPTY: &str = "_";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// Input ended inside a list, or contained no tree at all.
+    UnexpectedEnd,
+    /// A `)` with no list open.
+    UnexpectedClose,
+    /// A list must start with a value, not `(`, `)` or `_`.
+    ExpectedValue,
+    /// A list closed before both children were given.
+    MissingChild,
+    /// A list has more than two children.
+    ExpectedClose,
+    /// More input after the tree was complete.
+    TrailingInput,
+    /// The whole input is `_`.
+    EmptyTree,
+    /// The atom could not be parsed as a value.
+    InvalidValue(String),
+}
+
+/// A parse failure at a 1-based line and column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.kind {
+            ParseErrorKind::UnexpectedEnd => write!(f, "unexpected end of input"),
+            ParseErrorKind::UnexpectedClose => write!(f, "unexpected ')'"),
+            ParseErrorKind::ExpectedValue => write!(f, "expected a value after '('"),
+            ParseErrorKind::MissingChild => write!(f, "expected two children before ')'"),
+            ParseErrorKind::ExpectedClose => write!(f, "expected ')' after two children"),
+            ParseErrorKind::TrailingInput => write!(f, "unexpected input after the tree"),
+            ParseErrorKind::EmptyTree => write!(f, "tree is empty"),
+            ParseErrorKind::InvalidValue(atom) => write!(f, "invalid value '{}'", atom),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    Open,
+    Close,
+    Atom(&'a str),
+}
+
+/// A token with its 1-based line and column.
+struct Spanned<'a> {
+    token: Token<'a>,
+    line: usize,
+    column: usize,
+}
+
+fn tokenize(input: &str) -> Vec<Spanned<'_>> {
+    let mut tokens = Vec::new();
+
+    for (line_idx, line) in input.lines().enumerate() {
+        let mut chars = line.char_indices().peekable();
+        let mut column = 0;
+
+        while let Some((start, ch)) = chars.next() {
+            column += 1;
+            let token = match ch {
+                '(' => Token::Open,
+                ')' => Token::Close,
+                ch if ch.is_whitespace() => continue,
+                _ => {
+                    let start_column = column;
+                    let mut end = start + ch.len_utf8();
+                    while let Some(&(idx, next)) = chars.peek() {
+                        if next.is_whitespace() || next == '(' || next == ')' {
+                            break;
+                        }
+                        end = idx + next.len_utf8();
+                        column += 1;
+                        chars.next();
+                    }
+                    tokens.push(Spanned {
+                        token: Token::Atom(&line[start..end]),
+                        line: line_idx + 1,
+                        column: start_column,
+                    });
+                    continue;
+                }
+            };
+
+            tokens.push(Spanned {
+                token,
+                line: line_idx + 1,
+                column,
+            });
+        }
+    }
+
+    tokens
+}
+
+/// A list whose value has been read, waiting for its children.
+struct Frame<T> {
+    value: T,
+    children: Vec<Option<Rc<Node<T>>>>,
+}
+
+/// Parses a tree such as `(A (B D _) (C _ E))`.
+pub fn parse_tree<T: FromStr>(input: &str) -> Result<Rc<Node<T>>, ParseError> {
+    let mut tokens = tokenize(input).into_iter();
+    let mut stack: Vec<Frame<T>> = Vec::new();
+    let mut result: Option<Option<Rc<Node<T>>>> = None;
+
+    let (mut last_line, mut last_column) = (1, 1);
+    let (mut root_line, mut root_column) = (1, 1);
+
+    while let Some(Spanned {
+        token,
+        line,
+        column,
+    }) = tokens.next()
+    {
+        (last_line, last_column) = (line, column);
+        let error = |kind| ParseError { line, column, kind };
+
+        if stack.is_empty() && result.is_some() {
+            return Err(error(ParseErrorKind::TrailingInput));
+        }
+
+        let completed = match token {
+            Token::Open => {
+                let value = match tokens.next() {
+                    Some(Spanned {
+                        token: Token::Atom(atom),
+                        line,
+                        column,
+                    }) if atom != EMPTY => parse_value(atom, line, column)?,
+                    Some(Spanned { line, column, .. }) => {
+                        return Err(ParseError {
+                            line,
+                            column,
+                            kind: ParseErrorKind::ExpectedValue,
+                        });
+                    }
+                    None => return Err(error(ParseErrorKind::UnexpectedEnd)),
+                };
+                stack.push(Frame {
+                    value,
+                    children: Vec::with_capacity(2),
+                });
+                continue;
+            }
+            Token::Close => {
+                let frame = stack
+                    .pop()
+                    .ok_or_else(|| error(ParseErrorKind::UnexpectedClose))?;
+                let [left, right]: [Option<Rc<Node<T>>>; 2] = frame
+                    .children
+                    .try_into()
+                    .map_err(|_| error(ParseErrorKind::MissingChild))?;
+                Some(Rc::new(Node {
+                    value: frame.value,
+                    left,
+                    right,
+                }))
+            }
+            Token::Atom(EMPTY) => None,
+            Token::Atom(atom) => Some(Rc::new(Node::new(parse_value(atom, line, column)?))),
+        };
+
+        match stack.last_mut() {
+            Some(parent) if parent.children.len() == 2 => {
+                return Err(error(ParseErrorKind::ExpectedClose));
+            }
+            Some(parent) => parent.children.push(completed),
+            None => {
+                result = Some(completed);
+                (root_line, root_column) = (line, column);
+            }
+        }
+    }
+
+    match result {
+        Some(Some(root)) => Ok(root),
+        Some(None) => Err(ParseError {
+            line: root_line,
+            column: root_column,
+            kind: ParseErrorKind::EmptyTree,
+        }),
+        _ => Err(ParseError {
+            line: last_line,
+            column: last_column,
+            kind: ParseErrorKind::UnexpectedEnd,
+        }),
+    }
+}
+
+fn parse_value<T: FromStr>(atom: &str, line: usize, column: usize) -> Result<T, ParseError> {
+    atom.parse().map_err(|_| ParseError {
+        line,
+        column,
+        kind: ParseErrorKind::InvalidValue(atom.to_string()),
+    })
+}
+
+/// Pieces of output still to be written, innermost last.
+enum Pending<'a, T> {
+    Tree(Option<&'a Rc<Node<T>>>),
+    Text(&'static str),
+}
+
+/// Renders the S-expression form. Leaves are written as bare atoms.
+///
+/// Values must display as a single atom (no whitespace or parentheses)
+/// for the output to parse back.
+pub fn to_sexpr<T: fmt::Display>(root: &Rc<Node<T>>) -> String {
+    let mut out = String::new();
+    let mut stack = vec![Pending::Tree(Some(root))];
+
+    while let Some(pending) = stack.pop() {
+        match pending {
+            Pending::Text(text) => out.push_str(text),
+            Pending::Tree(None) => out.push_str(EMPTY),
+            Pending::Tree(Some(node)) if node.left.is_none() && node.right.is_none() => {
+                out.push_str(&node.value.to_string());
+            }
+            Pending::Tree(Some(node)) => {
+                out.push('(');
+                out.push_str(&node.value.to_string());
+                stack.extend([
+                    Pending::Text(")"),
+                    Pending::Tree(node.right.as_ref()),
+                    Pending::Text(" "),
+                    Pending::Tree(node.left.as_ref()),
+                    Pending::Text(" "),
+                ]);
+            }
+        }
+    }
+
+    out
+}
+
+/// Renders a box-drawing diagram, one node per line:
+///
+/// ```text
+/// A
+/// ├── B
+/// │   ├── D
+/// │   └── _
+/// └── C
+///     ├── _
+///     └── E
+/// ```
+///
+/// A node with one child shows `_` for the other, to keep left and right
+/// apart.
+pub fn to_ascii<T: fmt::Display>(root: &Rc<Node<T>>) -> String {
+    let mut out = format!("{}\n", root.value);
+    let mut stack = Vec::new();
+    push_children(&mut stack, root, "");
+
+    while let Some(DiagramLine {
+        node,
+        prefix,
+        is_last,
+    }) = stack.pop()
+    {
+        let (branch, indent) = if is_last {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+
+        out.push_str(&prefix);
+        out.push_str(branch);
+        match node {
+            None => out.push_str(EMPTY),
+            Some(node) => {
+                out.push_str(&node.value.to_string());
+                push_children(&mut stack, node, &format!("{}{}", prefix, indent));
+            }
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+/// A child still to be drawn, with the indentation of its line.
+struct DiagramLine<'a, T> {
+    node: Option<&'a Rc<Node<T>>>,
+    prefix: String,
+    is_last: bool,
+}
+
+fn push_children<'a, T>(stack: &mut Vec<DiagramLine<'a, T>>, node: &'a Rc<Node<T>>, prefix: &str) {
+    if node.left.is_none() && node.right.is_none() {
+        return;
+    }
+
+    stack.push(DiagramLine {
+        node: node.right.as_ref(),
+        prefix: prefix.to_string(),
+        is_last: true,
+    });
+    stack.push(DiagramLine {
+        node: node.left.as_ref(),
+        prefix: prefix.to_string(),
+        is_last: false,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traversal;
+
+    const TEST_TREE: &str = "(A (B D _) (C _ E))";
+
+    fn error_at(input: &str) -> (usize, usize, ParseErrorKind) {
+        let error = parse_tree::<String>(input).unwrap_err();
+        (error.line, error.column, error.kind)
+    }
+
+    #[test]
+    fn test_parse_and_render_round_trip() {
+        let root: Rc<Node<String>> = parse_tree(TEST_TREE).unwrap();
+
+        let values: Vec<String> = traversal::pre_order(&root)
+            .map(|node| node.value.clone())
+            .collect();
+        assert_eq!(values, ["A", "B", "D", "C", "E"]);
+        assert!(root.left.as_ref().unwrap().right.is_none());
+
+        assert_eq!(to_sexpr(&root), TEST_TREE);
+    }
+
+    #[test]
+    fn test_parse_numbers_across_lines() {
+        let root: Rc<Node<i32>> = parse_tree("(1\n  (2 _ 4)\n  3)").unwrap();
+
+        assert_eq!(to_sexpr(&root), "(1 (2 _ 4) 3)");
+        assert_eq!(to_sexpr(&parse_tree::<i32>("  7  ").unwrap()), "7");
+    }
+
+    #[test]
+    fn test_ascii_diagram() {
+        let root: Rc<Node<String>> = parse_tree(TEST_TREE).unwrap();
+
+        assert_eq!(
+            to_ascii(&root),
+            "A\n\
+             ├── B\n\
+             │   ├── D\n\
+             │   └── _\n\
+             └── C\n    \
+             ├── _\n    \
+             └── E\n"
+        );
+        assert_eq!(to_ascii(&Rc::new(Node::new(1))), "1\n");
+    }
+
+    #[test]
+    fn test_parse_errors_report_position() {
+        assert_eq!(error_at(""), (1, 1, ParseErrorKind::UnexpectedEnd));
+        assert_eq!(error_at("(A B"), (1, 4, ParseErrorKind::UnexpectedEnd));
+        assert_eq!(error_at("(A\n  B)"), (2, 4, ParseErrorKind::MissingChild));
+        assert_eq!(error_at("(A B C D)"), (1, 8, ParseErrorKind::ExpectedClose));
+        assert_eq!(error_at("( _ B C)"), (1, 3, ParseErrorKind::ExpectedValue));
+        assert_eq!(error_at("A)"), (1, 2, ParseErrorKind::TrailingInput));
+        assert_eq!(error_at(")"), (1, 1, ParseErrorKind::UnexpectedClose));
+        assert_eq!(error_at("\n  _"), (2, 3, ParseErrorKind::EmptyTree));
+
+        let error = parse_tree::<i32>("(1 2\n   x)").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::InvalidValue("x".to_string()));
+        assert_eq!(error.to_string(), "line 2, column 4: invalid value 'x'");
+    }
+
+    #[test]
+    fn test_deeply_nested_input() {
+        let depth = 100_000;
+        let input = format!("{}0{}", "(1 ".repeat(depth), " _)".repeat(depth));
+
+        let root: Rc<Node<i32>> = parse_tree(&input).unwrap();
+        assert_eq!(crate::count_nodes(&root), depth + 1);
+        assert_eq!(to_sexpr(&root), input);
+    }
+}
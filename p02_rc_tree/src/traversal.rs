@@ -0,0 +1,177 @@
+//! Tree Traversal Iterators
+//!
+//! Iterators over `Rc<Node<T>>` that keep their own explicit stack
+//! (or queue) instead of recursing, so a degenerate tree shaped like a
+//! linked list cannot overflow the call stack.
+
+use std::{collections::VecDeque, rc::Rc};
+
+use crate::Node;
+
+/// Root, left, right.
+pub struct PreOrder<T> {
+    stack: Vec<Rc<Node<T>>>,
+}
+
+/// Left, root, right.
+pub struct InOrder<T> {
+    stack: Vec<Rc<Node<T>>>,
+    curr: Option<Rc<Node<T>>>,
+}
+
+/// Left, right, root.
+pub struct PostOrder<T> {
+    // `true` once the node's children have been pushed
+    stack: Vec<(Rc<Node<T>>, bool)>,
+}
+
+/// Breadth-first, left to right.
+pub struct LevelOrder<T> {
+    queue: VecDeque<Rc<Node<T>>>,
+}
+
+pub fn pre_order<T>(root: &Rc<Node<T>>) -> PreOrder<T> {
+    PreOrder {
+        stack: vec![Rc::clone(root)],
+    }
+}
+
+pub fn in_order<T>(root: &Rc<Node<T>>) -> InOrder<T> {
+    InOrder {
+        stack: Vec::new(),
+        curr: Some(Rc::clone(root)),
+    }
+}
+
+pub fn post_order<T>(root: &Rc<Node<T>>) -> PostOrder<T> {
+    PostOrder {
+        stack: vec![(Rc::clone(root), false)],
+    }
+}
+
+pub fn level_order<T>(root: &Rc<Node<T>>) -> LevelOrder<T> {
+    LevelOrder {
+        queue: VecDeque::from([Rc::clone(root)]),
+    }
+}
+
+impl<T> Iterator for PreOrder<T> {
+    type Item = Rc<Node<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+
+        // Right first so that left is popped first
+        self.stack.extend(node.right.iter().cloned());
+        self.stack.extend(node.left.iter().cloned());
+
+        Some(node)
+    }
+}
+
+impl<T> Iterator for InOrder<T> {
+    type Item = Rc<Node<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.curr.take() {
+            self.curr = node.left.clone();
+            self.stack.push(node);
+        }
+
+        let node = self.stack.pop()?;
+        self.curr = node.right.clone();
+        Some(node)
+    }
+}
+
+impl<T> Iterator for PostOrder<T> {
+    type Item = Rc<Node<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((node, expanded)) = self.stack.pop() {
+            if expanded {
+                return Some(node);
+            }
+
+            let right = node.right.clone();
+            let left = node.left.clone();
+
+            self.stack.push((node, true));
+            self.stack.extend(right.map(|right| (right, false)));
+            self.stack.extend(left.map(|left| (left, false)));
+        }
+
+        None
+    }
+}
+
+impl<T> Iterator for LevelOrder<T> {
+    type Item = Rc<Node<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.queue.pop_front()?;
+
+        self.queue.extend(node.left.iter().cloned());
+        self.queue.extend(node.right.iter().cloned());
+
+        Some(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds this tree:
+    ///        A
+    ///       / \
+    ///      B   C
+    ///     / \   \
+    ///    D   F   E
+    fn build_test_tree() -> Rc<Node<&'static str>> {
+        let d = Rc::new(Node::new("D"));
+        let f = Rc::new(Node::new("F"));
+        let e = Rc::new(Node::new("E"));
+
+        let b = Rc::new(Node::with_children("B", d, f));
+        let c = Rc::new(Node::with_right_child("C", e));
+
+        Rc::new(Node::with_children("A", b, c))
+    }
+
+    fn values<I: Iterator<Item = Rc<Node<&'static str>>>>(iter: I) -> Vec<&'static str> {
+        iter.map(|node| node.value).collect()
+    }
+
+    #[test]
+    fn test_traversal_orders() {
+        let root = build_test_tree();
+
+        assert_eq!(values(pre_order(&root)), ["A", "B", "D", "F", "C", "E"]);
+        assert_eq!(values(in_order(&root)), ["D", "B", "F", "A", "C", "E"]);
+        assert_eq!(values(post_order(&root)), ["D", "F", "B", "E", "C", "A"]);
+        assert_eq!(values(level_order(&root)), ["A", "B", "C", "D", "F", "E"]);
+    }
+
+    #[test]
+    fn test_single_node() {
+        let root = Rc::new(Node::new("A"));
+
+        assert_eq!(values(pre_order(&root)), ["A"]);
+        assert_eq!(values(in_order(&root)), ["A"]);
+        assert_eq!(values(post_order(&root)), ["A"]);
+        assert_eq!(values(level_order(&root)), ["A"]);
+    }
+
+    #[test]
+    fn test_iterators_release_nodes() {
+        let root = build_test_tree();
+
+        let mut iter = pre_order(&root);
+        iter.next();
+        assert_eq!(Rc::strong_count(&root), 1);
+
+        drop(iter);
+        assert!(root.left.as_ref().is_some_and(|b| Rc::strong_count(b) == 1));
+    }
+}
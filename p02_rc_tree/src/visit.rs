@@ -0,0 +1,265 @@
+//! Visitors and Folds
+//!
+//! Generic walks over a tree. A `TreeVisitor` gets `enter`/`leave`
+//! callbacks with the depth and the path from the root, and can skip a
+//! subtree or stop the walk. `fold` accumulates over nodes in pre-order and
+//! can stop early through `ControlFlow`. Both use explicit stacks, so deep
+//! trees are fine.
+
+use std::{
+    ops::{Add, ControlFlow},
+    rc::Rc,
+};
+
+use crate::{Node, Side, traversal};
+
+/// What a visitor wants to happen after `enter`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisitControl {
+    Continue,
+    /// Don't visit this node's children. `leave` is still called for it.
+    SkipChildren,
+    /// End the walk now. No further callbacks are made.
+    Stop,
+}
+
+/// Callbacks for `walk`. `depth` is 0 at the root, and `path` holds the
+/// sides taken to reach the node (so `path.len() == depth`).
+pub trait TreeVisitor<T> {
+    fn enter(&mut self, _node: &Rc<Node<T>>, _depth: usize, _path: &[Side]) -> VisitControl {
+        VisitControl::Continue
+    }
+
+    /// Called after the node's children (if visited) are done.
+    fn leave(&mut self, _node: &Rc<Node<T>>, _depth: usize, _path: &[Side]) {}
+}
+
+enum Step<'a, T> {
+    Enter(&'a Rc<Node<T>>, Option<Side>),
+    Leave(&'a Rc<Node<T>>),
+}
+
+/// Walks the tree depth-first, left before right.
+/// Returns false if the visitor stopped the walk.
+pub fn walk<T, V: TreeVisitor<T>>(root: &Rc<Node<T>>, visitor: &mut V) -> bool {
+    let mut path = Vec::new();
+    let mut stack = vec![Step::Enter(root, None)];
+
+    while let Some(step) = stack.pop() {
+        match step {
+            Step::Enter(node, side) => {
+                path.extend(side);
+
+                let control = visitor.enter(node, path.len(), &path);
+                if control == VisitControl::Stop {
+                    return false;
+                }
+
+                stack.push(Step::Leave(node));
+                if control == VisitControl::Continue {
+                    stack.extend(
+                        node.right
+                            .as_ref()
+                            .map(|right| Step::Enter(right, Some(Side::Right))),
+                    );
+                    stack.extend(
+                        node.left
+                            .as_ref()
+                            .map(|left| Step::Enter(left, Some(Side::Left))),
+                    );
+                }
+            }
+            Step::Leave(node) => {
+                visitor.leave(node, path.len(), &path);
+                path.pop();
+            }
+        }
+    }
+
+    true
+}
+
+/// Folds over the nodes in pre-order. Returning `ControlFlow::Break` stops
+/// the fold and makes its value the result.
+pub fn fold<T, B, F>(root: &Rc<Node<T>>, init: B, mut f: F) -> B
+where
+    F: FnMut(B, &Rc<Node<T>>) -> ControlFlow<B, B>,
+{
+    match traversal::pre_order(root).try_fold(init, |acc, node| f(acc, &node)) {
+        ControlFlow::Continue(result) | ControlFlow::Break(result) => result,
+    }
+}
+
+/// Depth of the deepest node; the root alone has depth 0.
+pub fn max_depth<T>(root: &Rc<Node<T>>) -> usize {
+    struct MaxDepth(usize);
+
+    impl<T> TreeVisitor<T> for MaxDepth {
+        fn enter(&mut self, _node: &Rc<Node<T>>, depth: usize, _path: &[Side]) -> VisitControl {
+            self.0 = self.0.max(depth);
+            VisitControl::Continue
+        }
+    }
+
+    let mut visitor = MaxDepth(0);
+    walk(root, &mut visitor);
+    visitor.0
+}
+
+/// Number of levels in the tree; a single node has height 1.
+pub fn height<T>(root: &Rc<Node<T>>) -> usize {
+    max_depth(root) + 1
+}
+
+/// Adds up every value.
+pub fn sum<T>(root: &Rc<Node<T>>) -> T
+where
+    T: Clone + Default + Add<Output = T>,
+{
+    fold(root, T::default(), |total, node| {
+        ControlFlow::Continue(total + node.value.clone())
+    })
+}
+
+/// Sides from the root to the first node (in pre-order) holding `value`.
+pub fn path_to_value<T: PartialEq>(root: &Rc<Node<T>>, value: &T) -> Option<Vec<Side>> {
+    struct PathTo<'a, T> {
+        target: &'a T,
+        found: Option<Vec<Side>>,
+    }
+
+    impl<T: PartialEq> TreeVisitor<T> for PathTo<'_, T> {
+        fn enter(&mut self, node: &Rc<Node<T>>, _depth: usize, path: &[Side]) -> VisitControl {
+            if node.value != *self.target {
+                return VisitControl::Continue;
+            }
+            self.found = Some(path.to_vec());
+            VisitControl::Stop
+        }
+    }
+
+    let mut visitor = PathTo {
+        target: value,
+        found: None,
+    };
+    walk(root, &mut visitor);
+    visitor.found
+}
+
+/// Nodes without children, left to right.
+pub fn leaves<T>(root: &Rc<Node<T>>) -> Vec<Rc<Node<T>>> {
+    fold(root, Vec::new(), |mut leaves, node| {
+        if node.left.is_none() && node.right.is_none() {
+            leaves.push(Rc::clone(node));
+        }
+        ControlFlow::Continue(leaves)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text;
+
+    const TEST_TREE: &str = "(1 (2 4 _) (3 (5 7 _) 6))";
+
+    fn test_tree() -> Rc<Node<i32>> {
+        text::parse_tree(TEST_TREE).unwrap()
+    }
+
+    /// Records every callback as a string.
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<String>,
+        skip: Option<i32>,
+        stop: Option<i32>,
+    }
+
+    impl TreeVisitor<i32> for Recorder {
+        fn enter(&mut self, node: &Rc<Node<i32>>, depth: usize, path: &[Side]) -> VisitControl {
+            assert_eq!(path.len(), depth);
+            self.events.push(format!("+{}@{}", node.value, depth));
+            if Some(node.value) == self.stop {
+                VisitControl::Stop
+            } else if Some(node.value) == self.skip {
+                VisitControl::SkipChildren
+            } else {
+                VisitControl::Continue
+            }
+        }
+
+        fn leave(&mut self, node: &Rc<Node<i32>>, depth: usize, _path: &[Side]) {
+            self.events.push(format!("-{}@{}", node.value, depth));
+        }
+    }
+
+    #[test]
+    fn test_walk_enter_and_leave_order() {
+        let mut recorder = Recorder::default();
+
+        assert!(walk(&test_tree(), &mut recorder));
+        assert_eq!(
+            recorder.events.join(" "),
+            "+1@0 +2@1 +4@2 -4@2 -2@1 +3@1 +5@2 +7@3 -7@3 -5@2 +6@2 -6@2 -3@1 -1@0"
+        );
+    }
+
+    #[test]
+    fn test_walk_skip_and_stop() {
+        let mut recorder = Recorder {
+            skip: Some(3),
+            ..Recorder::default()
+        };
+        assert!(walk(&test_tree(), &mut recorder));
+        assert_eq!(
+            recorder.events.join(" "),
+            "+1@0 +2@1 +4@2 -4@2 -2@1 +3@1 -3@1 -1@0"
+        );
+
+        let mut recorder = Recorder {
+            stop: Some(4),
+            ..Recorder::default()
+        };
+        assert!(!walk(&test_tree(), &mut recorder));
+        assert_eq!(recorder.events.join(" "), "+1@0 +2@1 +4@2");
+    }
+
+    #[test]
+    fn test_fold_stops_early() {
+        let root = test_tree();
+
+        let mut seen = 0;
+        let first_over_four = fold(&root, None, |_, node| {
+            seen += 1;
+            if node.value > 4 {
+                ControlFlow::Break(Some(node.value))
+            } else {
+                ControlFlow::Continue(None)
+            }
+        });
+
+        assert_eq!(first_over_four, Some(5));
+        // Pre-order: 1, 2, 4, 3, 5
+        assert_eq!(seen, 5);
+    }
+
+    #[test]
+    fn test_built_in_algorithms() {
+        let root = test_tree();
+
+        assert_eq!(height(&root), 4);
+        assert_eq!(max_depth(&root), 3);
+        assert_eq!(height(&Rc::new(Node::new(0))), 1);
+        assert_eq!(sum(&root), 28);
+
+        assert_eq!(
+            path_to_value(&root, &7),
+            Some(vec![Side::Right, Side::Left, Side::Left])
+        );
+        assert_eq!(path_to_value(&root, &1), Some(vec![]));
+        assert_eq!(path_to_value(&root, &8), None);
+
+        let leaf_values: Vec<i32> = leaves(&root).iter().map(|node| node.value).collect();
+        assert_eq!(leaf_values, [4, 7, 6]);
+    }
+}
@@ -0,0 +1,231 @@
+//! Tree Zipper
+//!
+//! A cursor into an immutable `Rc<Node<T>>` tree. Moving down records the
+//! parent in a breadcrumb; edits replace only the focused node. Moving back
+//! up copies a parent only if the child below it changed, so `to_root`
+//! rebuilds just the modified spine and shares everything else.
+
+use std::rc::Rc;
+
+use crate::{Node, Side};
+
+/// The parent we came from and which of its children we went into.
+#[derive(Debug)]
+struct Crumb<T> {
+    parent: Rc<Node<T>>,
+    side: Side,
+}
+
+#[derive(Debug)]
+pub struct Zipper<T> {
+    focus: Rc<Node<T>>,
+    path: Vec<Crumb<T>>,
+}
+
+impl<T: Clone> Zipper<T> {
+    pub fn new(root: Rc<Node<T>>) -> Self {
+        Zipper {
+            focus: root,
+            path: Vec::new(),
+        }
+    }
+
+    /// The node under the cursor, including any edits made so far.
+    pub fn focus(&self) -> &Rc<Node<T>> {
+        &self.focus
+    }
+
+    pub fn value(&self) -> &T {
+        &self.focus.value
+    }
+
+    /// Number of steps below the root.
+    pub fn depth(&self) -> usize {
+        self.path.len()
+    }
+
+    /// Sides taken from the root to the focus.
+    pub fn path(&self) -> Vec<Side> {
+        self.path.iter().map(|crumb| crumb.side).collect()
+    }
+
+    /// Moves to the left child. Returns false (and stays put) if there is none.
+    pub fn go_left(&mut self) -> bool {
+        self.go(Side::Left)
+    }
+
+    /// Moves to the right child. Returns false (and stays put) if there is none.
+    pub fn go_right(&mut self) -> bool {
+        self.go(Side::Right)
+    }
+
+    pub fn go(&mut self, side: Side) -> bool {
+        let Some(child) = self.focus.child(side).cloned() else {
+            return false;
+        };
+
+        let parent = std::mem::replace(&mut self.focus, child);
+        self.path.push(Crumb { parent, side });
+        true
+    }
+
+    /// Moves to the parent. Returns false at the root.
+    pub fn up(&mut self) -> bool {
+        let Some(Crumb { parent, side }) = self.path.pop() else {
+            return false;
+        };
+
+        let unchanged = parent
+            .child(side)
+            .is_some_and(|child| Rc::ptr_eq(child, &self.focus));
+
+        self.focus = if unchanged {
+            parent
+        } else {
+            let child = Some(Rc::clone(&self.focus));
+            let (left, right) = match side {
+                Side::Left => (child, parent.right.clone()),
+                Side::Right => (parent.left.clone(), child),
+            };
+            Rc::new(Node {
+                value: parent.value.clone(),
+                left,
+                right,
+            })
+        };
+        true
+    }
+
+    /// Replaces the focused node's value, keeping its children.
+    pub fn replace_value(&mut self, value: T) {
+        self.focus = Rc::new(Node {
+            value,
+            left: self.focus.left.clone(),
+            right: self.focus.right.clone(),
+        });
+    }
+
+    /// Replaces (or with `None`, removes) one child of the focused node.
+    pub fn set_child(&mut self, side: Side, child: Option<Rc<Node<T>>>) {
+        let (left, right) = match side {
+            Side::Left => (child, self.focus.right.clone()),
+            Side::Right => (self.focus.left.clone(), child),
+        };
+
+        self.focus = Rc::new(Node {
+            value: self.focus.value.clone(),
+            left,
+            right,
+        });
+    }
+
+    /// Walks back to the root and returns it.
+    /// If nothing was edited, this is the original root.
+    pub fn to_root(mut self) -> Rc<Node<T>> {
+        while self.up() {}
+        self.focus
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traversal;
+
+    /// Builds this tree:
+    ///        A
+    ///       / \
+    ///      B   C
+    ///     /     \
+    ///    D       E
+    fn build_test_tree() -> Rc<Node<&'static str>> {
+        let d = Rc::new(Node::new("D"));
+        let e = Rc::new(Node::new("E"));
+
+        let b = Rc::new(Node::with_left_child("B", d));
+        let c = Rc::new(Node::with_right_child("C", e));
+
+        Rc::new(Node::with_children("A", b, c))
+    }
+
+    fn pre_order_values(root: &Rc<Node<&'static str>>) -> Vec<&'static str> {
+        traversal::pre_order(root).map(|node| node.value).collect()
+    }
+
+    #[test]
+    fn test_navigation() {
+        let mut zipper = Zipper::new(build_test_tree());
+
+        assert!(!zipper.up());
+        assert!(zipper.go_left());
+        assert!(!zipper.go_right());
+        assert!(zipper.go_left());
+        assert_eq!(*zipper.value(), "D");
+        assert_eq!(zipper.depth(), 2);
+        assert_eq!(zipper.path(), [Side::Left, Side::Left]);
+
+        assert!(zipper.up());
+        assert!(zipper.up());
+        assert!(zipper.go_right());
+        assert!(zipper.go_right());
+        assert_eq!(*zipper.value(), "E");
+    }
+
+    #[test]
+    fn test_unedited_walk_returns_original_root() {
+        let root = build_test_tree();
+
+        let mut zipper = Zipper::new(Rc::clone(&root));
+        zipper.go_right();
+        zipper.go_right();
+
+        assert!(Rc::ptr_eq(&zipper.to_root(), &root));
+    }
+
+    #[test]
+    fn test_replace_value_rebuilds_only_the_spine() {
+        let root = build_test_tree();
+
+        let mut zipper = Zipper::new(Rc::clone(&root));
+        zipper.go_right();
+        zipper.go_right();
+        zipper.replace_value("E2");
+        let new_root = zipper.to_root();
+
+        assert_eq!(pre_order_values(&root), ["A", "B", "D", "C", "E"]);
+        assert_eq!(pre_order_values(&new_root), ["A", "B", "D", "C", "E2"]);
+
+        // A and C were copied; the B subtree is shared
+        assert!(!Rc::ptr_eq(&root, &new_root));
+        assert!(!Rc::ptr_eq(
+            root.right.as_ref().unwrap(),
+            new_root.right.as_ref().unwrap()
+        ));
+        assert!(Rc::ptr_eq(
+            root.left.as_ref().unwrap(),
+            new_root.left.as_ref().unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_set_child_and_edit_below_it() {
+        let root = build_test_tree();
+
+        let mut zipper = Zipper::new(Rc::clone(&root));
+        zipper.go_left();
+        zipper.set_child(Side::Right, Some(Rc::new(Node::new("F"))));
+        zipper.go_right();
+        zipper.replace_value("G");
+        zipper.up();
+        zipper.set_child(Side::Left, None);
+        let new_root = zipper.to_root();
+
+        assert_eq!(pre_order_values(&new_root), ["A", "B", "G", "C", "E"]);
+        assert!(Rc::ptr_eq(
+            root.right.as_ref().unwrap(),
+            new_root.right.as_ref().unwrap()
+        ));
+        // Old version keeps its left child D
+        assert_eq!(pre_order_values(&root), ["A", "B", "D", "C", "E"]);
+    }
+}
@@ -0,0 +1,378 @@
+//! Mutable Cursor
+//!
+//! A position inside a `LinkedList` that can move both ways and edit the
+//! list around itself in O(1). Moving forward follows the `Rc` next link;
+//! moving backward upgrades the `Weak` prev link.
+//!
+//! Besides the elements there is one "ghost" position between the tail and
+//! the head, so a cursor can walk off either end and wrap around.
+
+use std::{
+    cell::{RefCell, RefMut},
+    rc::Rc,
+};
+
+use crate::{LinkedList, Node, into_value};
+
+#[derive(Debug)]
+pub struct CursorMut<'a, T> {
+    list: &'a mut LinkedList<T>,
+    /// `None` at the ghost position.
+    current: Option<Rc<RefCell<Node<T>>>>,
+    /// Index of `current`; equal to the list length at the ghost.
+    index: usize,
+}
+
+impl<T> LinkedList<T> {
+    /// Cursor at the first element (or the ghost, if the list is empty).
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut {
+            current: self.head.clone(),
+            index: 0,
+            list: self,
+        }
+    }
+
+    /// Cursor at the last element (or the ghost, if the list is empty).
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T> {
+        let current = self
+            .tail
+            .as_ref()
+            .map(|weak| weak.upgrade().expect("tail node should be alive"));
+        let index = self.len.saturating_sub(1);
+
+        CursorMut {
+            current,
+            index,
+            list: self,
+        }
+    }
+}
+
+// Same reason as for `ElementRef`
+impl<T> Drop for CursorMut<'_, T> {
+    fn drop(&mut self) {}
+}
+
+impl<'a, T> CursorMut<'a, T> {
+    /// Position of the current element, or `None` at the ghost.
+    pub fn index(&self) -> Option<usize> {
+        self.current.as_ref().map(|_| self.index)
+    }
+
+    pub fn current(&mut self) -> Option<RefMut<'_, T>> {
+        self.current
+            .as_ref()
+            .map(|node| RefMut::map(node.borrow_mut(), |node| &mut node.value))
+    }
+
+    /// Moves to the next element. From the tail this reaches the ghost;
+    /// from the ghost it wraps to the head.
+    pub fn move_next(&mut self) {
+        match self.current.take() {
+            Some(node) => {
+                self.current = node.borrow().next.clone();
+                self.index += 1;
+            }
+            None => {
+                self.current = self.list.head.clone();
+                self.index = 0;
+            }
+        }
+    }
+
+    /// Moves to the previous element. From the head this reaches the ghost;
+    /// from the ghost it wraps to the tail.
+    pub fn move_prev(&mut self) {
+        let prev = match self.current.take() {
+            Some(node) => node.borrow().prev.clone(),
+            None => self.list.tail.clone(),
+        };
+
+        self.current = prev.map(|weak| weak.upgrade().expect("prev node should be alive"));
+        self.index = match self.current {
+            Some(_) => self.index - 1,
+            None => self.list.len,
+        };
+    }
+
+    /// Inserts after the current element. At the ghost, inserts at the front.
+    pub fn insert_after(&mut self, value: T) {
+        match &self.current {
+            Some(node) => {
+                self.list.link_after(node, value);
+            }
+            None => {
+                self.list.push_front(value);
+                self.index += 1;
+            }
+        }
+    }
+
+    /// Inserts before the current element. At the ghost, inserts at the back.
+    pub fn insert_before(&mut self, value: T) {
+        match &self.current {
+            Some(node) => {
+                self.list.link_before(node, value);
+            }
+            None => {
+                self.list.push_back(value);
+            }
+        }
+        self.index += 1;
+    }
+
+    /// Removes the current element and moves to the next one.
+    /// Returns `None` (and does nothing) at the ghost.
+    pub fn remove_current(&mut self) -> Option<T> {
+        let node = self.current.take()?;
+        self.current = node.borrow().next.clone();
+        self.list.unlink(&node);
+        Some(into_value(node))
+    }
+
+    /// Moves all of `other`'s elements in right after the current element,
+    /// in O(1). At the ghost, they go to the front of the list.
+    pub fn splice_after(&mut self, mut other: LinkedList<T>) {
+        if self.current.is_none() {
+            self.index += other.len;
+        }
+        self.list.splice_in(self.current.as_ref(), &mut other);
+    }
+
+    /// Moves all of `other`'s elements in right before the current element,
+    /// in O(1). At the ghost, they go to the back of the list.
+    pub fn splice_before(&mut self, mut other: LinkedList<T>) {
+        let Some(node) = &self.current else {
+            self.list.append(&mut other);
+            self.index = self.list.len;
+            return;
+        };
+
+        let prev = node
+            .borrow()
+            .prev
+            .as_ref()
+            .map(|weak| weak.upgrade().expect("prev node should be alive"));
+        self.index += other.len;
+        self.list.splice_in(prev.as_ref(), &mut other);
+    }
+
+    /// Splits off everything after the current element into a new list.
+    /// At the ghost, the whole list is moved out. Takes time proportional
+    /// to the smaller half, whose nodes are relabelled with their new list.
+    pub fn split_after(&mut self) -> LinkedList<T> {
+        let Some(node) = &self.current else {
+            self.index = 0;
+            return std::mem::take(self.list);
+        };
+
+        let Some(rest) = node.borrow_mut().next.take() else {
+            return LinkedList::new();
+        };
+        rest.borrow_mut().prev = None;
+
+        let mut split = LinkedList {
+            head: Some(rest),
+            tail: self.list.tail.replace(Rc::downgrade(node)),
+            len: self.list.len - self.index - 1,
+            owner: Rc::default(),
+        };
+        self.list.len = self.index + 1;
+        self.list.relabel_smaller(&mut split);
+        split
+    }
+
+    /// Splits off everything before the current element into a new list.
+    /// Costs the same as `split_after`.
+    pub fn split_before(&mut self) -> LinkedList<T> {
+        let Some(node) = &self.current else {
+            self.index = 0;
+            return std::mem::take(self.list);
+        };
+
+        let Some(prev) = node.borrow_mut().prev.take() else {
+            return LinkedList::new();
+        };
+        let prev = prev.upgrade().expect("prev node should be alive");
+        prev.borrow_mut().next = None;
+
+        let mut split = LinkedList {
+            head: self.list.head.replace(Rc::clone(node)),
+            tail: Some(Rc::downgrade(&prev)),
+            len: self.index,
+            owner: Rc::default(),
+        };
+        self.list.len -= self.index;
+        self.index = 0;
+        self.list.relabel_smaller(&mut split);
+        split
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::list_of;
+
+    /// Checks both directions agree and returns the contents.
+    fn contents(list: &LinkedList<i32>) -> Vec<i32> {
+        let forward = list.traverse_forward();
+        let mut backward = list.traverse_backward();
+        backward.reverse();
+
+        assert_eq!(forward, backward);
+        assert_eq!(forward.len(), list.len());
+        forward
+    }
+
+    #[test]
+    fn test_move_both_ways_through_the_ghost() {
+        let mut list = list_of(&[1, 2, 3]);
+        let mut cursor = list.cursor_front_mut();
+
+        assert_eq!(cursor.index(), Some(0));
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(*cursor.current().unwrap(), 3);
+        cursor.move_next();
+        assert_eq!(cursor.index(), None);
+        assert!(cursor.current().is_none());
+        cursor.move_next();
+        assert_eq!((cursor.index(), *cursor.current().unwrap()), (Some(0), 1));
+
+        cursor.move_prev();
+        assert_eq!(cursor.index(), None);
+        cursor.move_prev();
+        assert_eq!((cursor.index(), *cursor.current().unwrap()), (Some(2), 3));
+        cursor.move_prev();
+        assert_eq!((cursor.index(), *cursor.current().unwrap()), (Some(1), 2));
+
+        let mut list = list_of(&[1, 2, 3]);
+        let mut cursor = list.cursor_back_mut();
+        *cursor.current().unwrap() = 30;
+        assert_eq!(cursor.index(), Some(2));
+        drop(cursor);
+        assert_eq!(contents(&list), [1, 2, 30]);
+    }
+
+    #[test]
+    fn test_insert_around_the_cursor() {
+        let mut list = list_of(&[1, 3]);
+        let mut cursor = list.cursor_front_mut();
+
+        cursor.insert_after(2);
+        cursor.insert_before(0);
+        assert_eq!((cursor.index(), *cursor.current().unwrap()), (Some(1), 1));
+
+        cursor.move_prev();
+        cursor.move_prev();
+        // At the ghost: after means front, before means back
+        cursor.insert_after(-1);
+        cursor.insert_before(4);
+        assert_eq!(cursor.index(), None);
+
+        cursor.move_prev();
+        cursor.insert_after(5);
+        assert_eq!((cursor.index(), *cursor.current().unwrap()), (Some(5), 4));
+
+        drop(cursor);
+        assert_eq!(contents(&list), [-1, 0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_remove_current() {
+        let mut list = list_of(&[1, 2, 3, 4]);
+        let mut cursor = list.cursor_front_mut();
+
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(2));
+        assert_eq!((cursor.index(), *cursor.current().unwrap()), (Some(1), 3));
+
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(4));
+        assert_eq!(cursor.index(), None);
+        assert_eq!(cursor.remove_current(), None);
+
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(1));
+        drop(cursor);
+        assert_eq!(contents(&list), [3]);
+
+        let mut cursor = list.cursor_front_mut();
+        assert_eq!(cursor.remove_current(), Some(3));
+        drop(cursor);
+        assert!(list.is_empty());
+        assert!(list.head.is_none() && list.tail.is_none());
+    }
+
+    #[test]
+    fn test_split_after_and_before() {
+        let mut list = list_of(&[1, 2, 3, 4, 5]);
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+
+        let after = cursor.split_after();
+        assert!(cursor.split_after().is_empty());
+        drop(cursor);
+        assert_eq!(contents(&after), [3, 4, 5]);
+        assert_eq!(contents(&list), [1, 2]);
+
+        let mut list = list_of(&[1, 2, 3, 4, 5]);
+        let mut cursor = list.cursor_back_mut();
+        cursor.move_prev();
+
+        let before = cursor.split_before();
+        assert_eq!(cursor.index(), Some(0));
+        assert!(cursor.split_before().is_empty());
+        cursor.insert_before(0);
+        drop(cursor);
+        assert_eq!(contents(&before), [1, 2, 3]);
+        assert_eq!(contents(&list), [0, 4, 5]);
+
+        let mut cursor = list.cursor_back_mut();
+        cursor.move_next();
+        let everything = cursor.split_after();
+        drop(cursor);
+        assert_eq!(contents(&everything), [0, 4, 5]);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn test_splice_after_and_before() {
+        let mut list = list_of(&[1, 5]);
+        let mut cursor = list.cursor_front_mut();
+
+        cursor.splice_after(list_of(&[2, 3]));
+        assert_eq!((cursor.index(), *cursor.current().unwrap()), (Some(0), 1));
+        cursor.move_next();
+        cursor.move_next();
+        cursor.move_next();
+        cursor.splice_before(list_of(&[4]));
+        assert_eq!((cursor.index(), *cursor.current().unwrap()), (Some(4), 5));
+
+        cursor.splice_after(LinkedList::new());
+        cursor.splice_before(LinkedList::new());
+        cursor.splice_after(list_of(&[6]));
+        drop(cursor);
+        assert_eq!(contents(&list), [1, 2, 3, 4, 5, 6]);
+
+        // At the ghost: after means front, before means back
+        let mut cursor = list.cursor_back_mut();
+        cursor.move_next();
+        cursor.splice_after(list_of(&[-1, 0]));
+        cursor.splice_before(list_of(&[7]));
+        assert_eq!(cursor.index(), None);
+        cursor.move_prev();
+        assert_eq!((cursor.index(), *cursor.current().unwrap()), (Some(8), 7));
+        drop(cursor);
+        assert_eq!(contents(&list), [-1, 0, 1, 2, 3, 4, 5, 6, 7]);
+
+        let mut empty = LinkedList::new();
+        let mut cursor = empty.cursor_front_mut();
+        cursor.splice_before(list_of(&[1, 2]));
+        assert_eq!(cursor.index(), None);
+        drop(cursor);
+        assert_eq!(contents(&empty), [1, 2]);
+    }
+}
@@ -0,0 +1,379 @@
+//! Node Handles
+//!
+//! `push_back` and `push_front` return a `NodeHandle`: a `Weak` reference to
+//! the new node. With it, the node can later be removed, or a value
+//! inserted after it, in O(1) without searching. A handle does not keep its
+//! node alive, so using one after the node is gone is an error, not a
+//! dangling pointer.
+//!
+//! Each node also records which list it belongs to (an `Owner`), so a
+//! handle used with the wrong list, for example after `split_off` moved its
+//! node elsewhere, is rejected instead of corrupting both lists.
+
+use std::{
+    cell::RefCell,
+    error::Error,
+    fmt,
+    rc::{Rc, Weak},
+};
+
+use crate::{LinkedList, Node, into_value};
+
+/// Refers to one node of a list without owning it.
+#[derive(Debug)]
+pub struct NodeHandle<T> {
+    node: Weak<RefCell<Node<T>>>,
+}
+
+impl<T> NodeHandle<T> {
+    pub(crate) fn new(node: &Rc<RefCell<Node<T>>>) -> Self {
+        NodeHandle {
+            node: Rc::downgrade(node),
+        }
+    }
+
+    /// Whether the node still exists.
+    pub fn is_valid(&self) -> bool {
+        self.node.strong_count() > 0
+    }
+
+    pub(crate) fn upgrade(&self) -> Result<Rc<RefCell<Node<T>>>, HandleError> {
+        self.node.upgrade().ok_or(HandleError::Stale)
+    }
+}
+
+// Derived Clone would require T: Clone
+impl<T> Clone for NodeHandle<T> {
+    fn clone(&self) -> Self {
+        NodeHandle {
+            node: Weak::clone(&self.node),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandleError {
+    /// The node was removed from its list and freed.
+    Stale,
+    /// The node is alive but belongs to a different list.
+    ForeignList,
+}
+
+impl fmt::Display for HandleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HandleError::Stale => write!(f, "the node behind this handle no longer exists"),
+            HandleError::ForeignList => {
+                write!(f, "the node behind this handle belongs to another list")
+            }
+        }
+    }
+}
+
+impl Error for HandleError {}
+
+/// Identity of a list, recorded in each of its nodes.
+///
+/// Appending would have to relabel every moved node, so instead the emptied
+/// list's owner is forwarded to the receiving list's, as in a union-find.
+/// Only the root of a forwarding chain is held by a list.
+#[derive(Debug, Default)]
+pub(crate) struct Owner {
+    forward: RefCell<Option<Rc<Owner>>>,
+}
+
+impl Owner {
+    /// Forwards `from` to `to` and gives `from` a fresh identity.
+    pub(crate) fn forward(from: &mut Rc<Owner>, to: &Rc<Owner>) {
+        let old = std::mem::take(from);
+        *old.forward.borrow_mut() = Some(Rc::clone(to));
+    }
+
+    /// Follows the forwards to the root without changing anything.
+    fn root(owner: &Rc<Owner>) -> Rc<Owner> {
+        let mut root = Rc::clone(owner);
+        loop {
+            let next = root.forward.borrow().clone();
+            match next {
+                Some(next) => root = next,
+                None => return root,
+            }
+        }
+    }
+
+    /// Like `root`, but also points every owner on the way straight at it.
+    fn resolve(owner: &Rc<Owner>) -> Rc<Owner> {
+        let root = Self::root(owner);
+        let mut curr = Rc::clone(owner);
+        while !Rc::ptr_eq(&curr, &root) {
+            curr = curr
+                .forward
+                .replace(Some(Rc::clone(&root)))
+                .expect("only the root has no forward");
+        }
+        root
+    }
+}
+
+// Forwarding chains can get long, so free them in a loop as well
+impl Drop for Owner {
+    fn drop(&mut self) {
+        let mut next = self.forward.get_mut().take();
+        while let Some(owner) = next {
+            next = Rc::try_unwrap(owner)
+                .ok()
+                .and_then(|mut owner| owner.forward.get_mut().take());
+        }
+    }
+}
+
+impl<T> LinkedList<T> {
+    /// Removes the node behind `handle` and returns its value.
+    pub fn remove(&mut self, handle: &NodeHandle<T>) -> Result<T, HandleError> {
+        let node = self.node_of(handle)?;
+
+        self.unlink(&node);
+        Ok(into_value(node))
+    }
+
+    /// Inserts `value` right after the node behind `handle`.
+    pub fn insert_after(
+        &mut self,
+        handle: &NodeHandle<T>,
+        value: T,
+    ) -> Result<NodeHandle<T>, HandleError> {
+        let node = self.node_of(handle)?;
+
+        Ok(NodeHandle::new(&self.link_after(&node, value)))
+    }
+
+    /// Moves the node behind `handle` to the front. The node itself is
+    /// relinked, not copied, so every handle to it stays valid.
+    pub fn move_to_front(&mut self, handle: &NodeHandle<T>) -> Result<(), HandleError> {
+        let node = self.node_of(handle)?;
+
+        if self
+            .head
+            .as_ref()
+            .is_some_and(|head| Rc::ptr_eq(head, &node))
+        {
+            return Ok(());
+        }
+        self.unlink(&node);
+        self.link_front(node);
+        Ok(())
+    }
+
+    /// The node behind `handle`, if it is alive and in this list.
+    fn node_of(&self, handle: &NodeHandle<T>) -> Result<Rc<RefCell<Node<T>>>, HandleError> {
+        let node = handle.upgrade()?;
+        if !self.owns(&node) {
+            return Err(HandleError::ForeignList);
+        }
+        Ok(node)
+    }
+
+    /// Whether `node` is labelled with this list. Amortized O(1).
+    pub(crate) fn owns(&self, node: &Rc<RefCell<Node<T>>>) -> bool {
+        let mut node = node.borrow_mut();
+        let Some(owner) = &node.owner else {
+            return false;
+        };
+
+        let root = Owner::resolve(owner);
+        let owned = Rc::ptr_eq(&root, &self.owner);
+        node.owner = Some(root);
+        owned
+    }
+
+    /// Same check as `owns`, but leaves the labels alone, so it works while
+    /// the node is borrowed. O(length of the forwarding chain).
+    pub(crate) fn labels(&self, node: &Node<T>) -> bool {
+        node.owner
+            .as_ref()
+            .is_some_and(|owner| Rc::ptr_eq(&Owner::root(owner), &self.owner))
+    }
+
+    /// After a split into `self` and `other` (which has a fresh owner),
+    /// relabels the smaller of the two; the larger keeps `self`'s owner.
+    pub(crate) fn relabel_smaller(&mut self, other: &mut LinkedList<T>) {
+        if self.len < other.len {
+            std::mem::swap(&mut self.owner, &mut other.owner);
+            self.relabel();
+        } else {
+            other.relabel();
+        }
+    }
+
+    fn relabel(&self) {
+        let mut curr = self.head.clone();
+        while let Some(node) = curr {
+            let mut node = node.borrow_mut();
+            node.owner = Some(Rc::clone(&self.owner));
+            curr = node.next.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remove_by_handle() {
+        let mut list = LinkedList::new();
+        let a = list.push_back('a');
+        let b = list.push_back('b');
+        let c = list.push_back('c');
+        let z = list.push_front('z');
+
+        assert_eq!(list.remove(&b), Ok('b'));
+        assert_eq!(list.traverse_forward(), ['z', 'a', 'c']);
+
+        assert_eq!(list.remove(&z), Ok('z'));
+        assert_eq!(list.remove(&c), Ok('c'));
+        assert_eq!(list.traverse_backward(), ['a']);
+
+        assert_eq!(list.remove(&a), Ok('a'));
+        assert!(list.is_empty());
+        assert!(list.head.is_none() && list.tail.is_none());
+    }
+
+    #[test]
+    fn test_insert_after_handle() {
+        let mut list = LinkedList::new();
+        let first = list.push_back(1);
+        let last = list.push_back(3);
+
+        let second = list.insert_after(&first, 2).unwrap();
+        let fourth = list.insert_after(&last, 4).unwrap();
+        list.insert_after(&second, 25).unwrap();
+
+        assert_eq!(list.traverse_forward(), [1, 2, 25, 3, 4]);
+        assert_eq!(list.traverse_backward(), [4, 3, 25, 2, 1]);
+        assert_eq!(*list.back().unwrap().borrow(), 4);
+        assert_eq!(list.len(), 5);
+
+        assert_eq!(list.remove(&fourth), Ok(4));
+        assert_eq!(list.pop_back(), Some(3));
+    }
+
+    #[test]
+    fn test_move_to_front_keeps_handles() {
+        let mut list = LinkedList::new();
+        let a = list.push_back('a');
+        let b = list.push_back('b');
+        let c = list.push_back('c');
+
+        list.move_to_front(&c).unwrap();
+        assert_eq!(list.traverse_forward(), ['c', 'a', 'b']);
+        list.move_to_front(&c).unwrap();
+        list.move_to_front(&a).unwrap();
+        assert_eq!(list.traverse_backward(), ['b', 'c', 'a']);
+        assert_eq!(list.len(), 3);
+
+        // The moved nodes are the same ones, so their handles still work
+        assert_eq!(list.remove(&c), Ok('c'));
+        assert_eq!(list.remove(&a), Ok('a'));
+        list.move_to_front(&b).unwrap();
+        assert_eq!(list.traverse_forward(), ['b']);
+
+        list.remove(&b).unwrap();
+        assert_eq!(list.move_to_front(&b), Err(HandleError::Stale));
+    }
+
+    #[test]
+    fn test_stale_handles_are_rejected() {
+        let mut list = LinkedList::new();
+        let a = list.push_back(String::from("a"));
+        let b = list.push_back(String::from("b"));
+        let b_copy = b.clone();
+
+        assert_eq!(list.pop_front().as_deref(), Some("a"));
+        assert!(!a.is_valid());
+        assert_eq!(list.remove(&a), Err(HandleError::Stale));
+        assert_eq!(
+            list.insert_after(&a, String::from("x")).unwrap_err(),
+            HandleError::Stale
+        );
+
+        assert_eq!(list.remove(&b).as_deref(), Ok("b"));
+        assert_eq!(list.remove(&b_copy), Err(HandleError::Stale));
+        assert!(list.is_empty());
+
+        assert_eq!(
+            HandleError::Stale.to_string(),
+            "the node behind this handle no longer exists"
+        );
+    }
+
+    #[test]
+    fn test_foreign_handles_are_rejected() {
+        let mut a = LinkedList::new();
+        let mut b = LinkedList::new();
+        a.push_back(1);
+        b.push_back(10);
+        let middle = b.push_back(20);
+        b.push_back(30);
+
+        assert_eq!(a.remove(&middle), Err(HandleError::ForeignList));
+        assert_eq!(
+            a.insert_after(&middle, 2).unwrap_err(),
+            HandleError::ForeignList
+        );
+        assert_eq!(a.move_to_front(&middle), Err(HandleError::ForeignList));
+
+        // Neither list was touched
+        assert_eq!(a.traverse_forward(), [1]);
+        assert_eq!(b.traverse_forward(), [10, 20, 30]);
+        assert_eq!((a.validate(), b.validate()), (Ok(()), Ok(())));
+        assert_eq!(
+            HandleError::ForeignList.to_string(),
+            "the node behind this handle belongs to another list"
+        );
+    }
+
+    #[test]
+    fn test_handles_follow_their_nodes() {
+        let mut list = LinkedList::new();
+        let handles: Vec<_> = (0..10).map(|i| list.push_back(i)).collect();
+
+        // Small tail moves out, then small head: both halves get relabelled
+        let mut tail = list.split_off(8);
+        let mut head = list.split_off(0);
+        let mut rest = head.split_off(2);
+        assert_eq!(head.remove(&handles[9]), Err(HandleError::ForeignList));
+        assert_eq!(rest.remove(&handles[1]), Err(HandleError::ForeignList));
+        assert_eq!(tail.remove(&handles[9]), Ok(9));
+        assert_eq!(head.remove(&handles[1]), Ok(1));
+        assert_eq!(rest.remove(&handles[5]), Ok(5));
+
+        // Appending and splicing hand the nodes over to the receiving list
+        rest.append(&mut tail);
+        rest.cursor_front_mut().splice_after(head);
+        assert_eq!(rest.traverse_forward(), [2, 0, 3, 4, 6, 7, 8]);
+        assert_eq!(tail.remove(&handles[8]), Err(HandleError::ForeignList));
+        assert_eq!(rest.remove(&handles[8]), Ok(8));
+        assert_eq!(rest.remove(&handles[0]), Ok(0));
+        rest.move_to_front(&handles[7]).unwrap();
+        assert_eq!(rest.traverse_forward(), [7, 2, 3, 4, 6]);
+        assert_eq!(rest.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_long_forwarding_chains() {
+        // Each append forwards the growing list's owner once more
+        let mut list = LinkedList::new();
+        let first = list.push_back(0);
+        for i in 1..200_000 {
+            let mut next = LinkedList::new();
+            next.push_back(i);
+            next.append(&mut list);
+            list = next;
+        }
+
+        assert_eq!(list.remove(&first), Ok(0));
+        assert_eq!(list.len(), 199_999);
+        drop(list);
+    }
+}
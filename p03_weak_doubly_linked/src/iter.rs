@@ -0,0 +1,202 @@
+//! Iterators
+//!
+//! `iter()` walks the list without cloning values: each item is an
+//! `ElementRef` guard to borrow from. `into_iter()` moves the values out
+//! by popping nodes. Both work from either end.
+
+use std::{cell::RefCell, marker::PhantomData, rc::Rc};
+
+use crate::{ElementRef, LinkedList, Node};
+
+/// Borrowing iterator; see `LinkedList::iter`.
+#[derive(Debug)]
+pub struct Iter<'a, T> {
+    front: Option<Rc<RefCell<Node<T>>>>,
+    back: Option<Rc<RefCell<Node<T>>>>,
+    /// Elements between `front` and `back`, inclusive.
+    remaining: usize,
+    _list: PhantomData<&'a LinkedList<T>>,
+}
+
+impl<T> LinkedList<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            front: self.head.clone(),
+            back: self.tail.as_ref().and_then(|weak| weak.upgrade()),
+            remaining: self.len,
+            _list: PhantomData,
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = ElementRef<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let node = self.front.take()?;
+        self.front = node.borrow().next.clone();
+        self.remaining -= 1;
+        Some(ElementRef::new(node))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let node = self.back.take()?;
+        self.back = node.borrow().prev.as_ref().and_then(|weak| weak.upgrade());
+        self.remaining -= 1;
+        Some(ElementRef::new(node))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<'a, T> IntoIterator for &'a LinkedList<T> {
+    type Item = ElementRef<'a, T>;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Owning iterator; values that are not consumed are dropped with it.
+#[derive(Debug)]
+pub struct IntoIter<T> {
+    list: LinkedList<T>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.list.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len(), Some(self.list.len()))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.list.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for LinkedList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { list: self }
+    }
+}
+
+impl<T> Extend<T> for LinkedList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push_back(value);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for LinkedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = LinkedList::new();
+        list.extend(iter);
+        list
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    #[test]
+    fn test_iter_both_directions() {
+        let list: LinkedList<i32> = (1..=5).collect();
+
+        let forward: Vec<i32> = list.iter().map(|value| *value.borrow()).collect();
+        let backward: Vec<i32> = list.iter().rev().map(|value| *value.borrow()).collect();
+        assert_eq!(forward, [1, 2, 3, 4, 5]);
+        assert_eq!(backward, [5, 4, 3, 2, 1]);
+
+        // Both ends meet in the middle without yielding anything twice
+        let mut iter = list.iter();
+        assert_eq!(*iter.next().unwrap().borrow(), 1);
+        assert_eq!(*iter.next_back().unwrap().borrow(), 5);
+        assert_eq!(*iter.next().unwrap().borrow(), 2);
+        assert_eq!(*iter.next_back().unwrap().borrow(), 4);
+        assert_eq!(iter.len(), 1);
+        assert_eq!(*iter.next_back().unwrap().borrow(), 3);
+        assert!(iter.next().is_none());
+        assert!(iter.next_back().is_none());
+    }
+
+    #[test]
+    fn test_iter_does_not_clone() {
+        // String is Clone, but nothing here needs it
+        let list: LinkedList<String> = ["a", "bb", "ccc"].into_iter().map(String::from).collect();
+
+        let mut total = 0;
+        for value in &list {
+            total += value.borrow().len();
+        }
+        assert_eq!(total, 6);
+        assert!(LinkedList::<String>::new().iter().next().is_none());
+    }
+
+    #[test]
+    fn test_into_iter_moves_values_out() {
+        struct DropCounter<'a>(&'a Cell<usize>, u32);
+
+        impl Drop for DropCounter<'_> {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let drop_count = Cell::new(0);
+        let list: LinkedList<DropCounter> = (0..4).map(|id| DropCounter(&drop_count, id)).collect();
+
+        let mut iter = list.into_iter();
+        let first = iter.next().unwrap();
+        let last = iter.next_back().unwrap();
+        assert_eq!((first.1, last.1), (0, 3));
+        assert_eq!(drop_count.get(), 0);
+
+        // The two values left in the iterator are dropped with it
+        drop(iter);
+        assert_eq!(drop_count.get(), 2);
+        drop((first, last));
+        assert_eq!(drop_count.get(), 4);
+    }
+
+    #[test]
+    fn test_extend_and_collect() {
+        let mut list: LinkedList<i32> = vec![1, 2].into_iter().collect();
+        list.extend([3, 4]);
+        list.push_front(0);
+
+        assert_eq!(list.len(), 5);
+        assert_eq!(list.traverse_backward(), [4, 3, 2, 1, 0]);
+        assert_eq!(list.into_iter().rev().collect::<Vec<_>>(), [4, 3, 2, 1, 0]);
+    }
+}
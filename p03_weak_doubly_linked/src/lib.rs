@@ -1,13 +1,28 @@
+pub mod cursor;
+pub mod handle;
+pub mod iter;
+pub mod lru;
+pub mod sort;
+pub mod sync_list;
+#[cfg(test)]
+mod test_util;
+pub mod validate;
+
 use std::{
-    cell::RefCell,
+    cell::{Ref, RefCell},
+    marker::PhantomData,
     rc::{Rc, Weak},
 };
 
+use handle::{NodeHandle, Owner};
+
 #[derive(Debug)]
 pub struct Node<T> {
     pub value: T,
-    pub next: Option<Rc<RefCell<Node<T>>>>,
-    pub prev: Option<Weak<RefCell<Node<T>>>>,
+    pub(crate) next: Option<Rc<RefCell<Node<T>>>>,
+    pub(crate) prev: Option<Weak<RefCell<Node<T>>>>,
+    /// The list the node was linked into; see `handle::Owner`.
+    owner: Option<Rc<Owner>>,
 }
 
 impl<T> Node<T> {
@@ -16,14 +31,62 @@ impl<T> Node<T> {
             value,
             next: None,
             prev: None,
+            owner: None,
         }
     }
 }
 
 #[derive(Debug)]
 pub struct LinkedList<T> {
-    pub head: Option<Rc<RefCell<Node<T>>>>,
-    pub tail: Option<Weak<RefCell<Node<T>>>>,
+    // Not public: an outside clone of a node would make removing it panic.
+    // Use `front`, `back` and `iter` instead.
+    pub(crate) head: Option<Rc<RefCell<Node<T>>>>,
+    pub(crate) tail: Option<Weak<RefCell<Node<T>>>>,
+    len: usize,
+    owner: Rc<Owner>,
+}
+
+/// Shared access to one element of a list.
+///
+/// Only the head is owned by the list directly; every other node is reached
+/// through a `Weak` or a neighbour's `RefCell`, so a plain `Ref` could not
+/// outlive the lookup. The guard keeps the node alive instead, and borrows
+/// the list so it cannot be modified meanwhile.
+///
+/// The list stays borrowed until the guard is dropped, so a guard can't
+/// hold a node alive while that node is removed:
+///
+/// ```compile_fail
+/// use p03_weak_doubly_linked::LinkedList;
+///
+/// let mut list = LinkedList::new();
+/// list.push_back(1);
+/// let front = list.front();
+/// list.pop_front();
+/// ```
+#[derive(Debug)]
+pub struct ElementRef<'a, T> {
+    node: Rc<RefCell<Node<T>>>,
+    _list: PhantomData<&'a LinkedList<T>>,
+}
+
+impl<T> ElementRef<'_, T> {
+    fn new(node: Rc<RefCell<Node<T>>>) -> Self {
+        ElementRef {
+            node,
+            _list: PhantomData,
+        }
+    }
+
+    pub fn borrow(&self) -> Ref<'_, T> {
+        Ref::map(self.node.borrow(), |node| &node.value)
+    }
+}
+
+// Keeps the list borrowed until the guard is dropped, not just until its
+// last use, so the node it holds can't block removal from the list.
+impl<T> Drop for ElementRef<'_, T> {
+    fn drop(&mut self) {}
 }
 
 impl<T> LinkedList<T> {
@@ -31,11 +94,21 @@ impl<T> LinkedList<T> {
         LinkedList {
             head: None,
             tail: None,
+            len: 0,
+            owner: Rc::default(),
         }
     }
 
-    pub fn push_back(&mut self, value: T) {
-        let node = Rc::new(RefCell::new(Node::new(value)));
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push_back(&mut self, value: T) -> NodeHandle<T> {
+        let node = self.new_node(value);
 
         match self.tail.take() {
             Some(old_tail_weak) => {
@@ -48,42 +121,232 @@ impl<T> LinkedList<T> {
             None => self.head = Some(Rc::clone(&node)),
         }
 
-        self.tail = Some(Rc::downgrade(&node))
+        self.tail = Some(Rc::downgrade(&node));
+        self.len += 1;
+        NodeHandle::new(&node)
+    }
+
+    pub fn push_front(&mut self, value: T) -> NodeHandle<T> {
+        let node = self.new_node(value);
+        let handle = NodeHandle::new(&node);
+        self.link_front(node);
+        handle
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        let old_head = self.head.clone()?;
+        self.unlink(&old_head);
+        Some(into_value(old_head))
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        let old_tail = self
+            .tail
+            .as_ref()?
+            .upgrade()
+            .expect("tail node should be alive");
+        self.unlink(&old_tail);
+        Some(into_value(old_tail))
+    }
+
+    pub fn front(&self) -> Option<ElementRef<'_, T>> {
+        self.head.clone().map(ElementRef::new)
+    }
+
+    pub fn back(&self) -> Option<ElementRef<'_, T>> {
+        self.tail
+            .as_ref()
+            .map(|weak| ElementRef::new(weak.upgrade().expect("tail node should be alive")))
+    }
+
+    /// Moves all of `other`'s elements to the end of this list, in O(1).
+    /// `other` is left empty.
+    pub fn append(&mut self, other: &mut LinkedList<T>) {
+        let tail = self
+            .tail
+            .as_ref()
+            .map(|weak| weak.upgrade().expect("tail node should be alive"));
+        self.splice_in(tail.as_ref(), other);
+    }
+
+    /// Splits the list in two at `at`: this list keeps `[0, at)` and the
+    /// rest is returned. Walks from whichever end is closer.
+    /// Panics if `at > len`.
+    pub fn split_off(&mut self, at: usize) -> LinkedList<T> {
+        let len = self.len;
+        assert!(at <= len, "split index {at} out of bounds for length {len}");
+
+        if at == 0 {
+            return std::mem::take(self);
+        }
+
+        // Stand on the last element that stays
+        let mut cursor = if at <= len / 2 {
+            let mut cursor = self.cursor_front_mut();
+            (1..at).for_each(|_| cursor.move_next());
+            cursor
+        } else {
+            let mut cursor = self.cursor_back_mut();
+            (at..len).for_each(|_| cursor.move_prev());
+            cursor
+        };
+        cursor.split_after()
+    }
+
+    /// Removes every element, one node at a time.
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    /// Moves all of `other`'s nodes in right after `prev` (at the front for
+    /// `None`), in O(1). `other` is left empty.
+    fn splice_in(&mut self, prev: Option<&Rc<RefCell<Node<T>>>>, other: &mut LinkedList<T>) {
+        let Some(first) = other.head.take() else {
+            return;
+        };
+        let last = other
+            .tail
+            .take()
+            .and_then(|weak| weak.upgrade())
+            .expect("tail node should be alive");
+
+        let next = match prev {
+            Some(prev) => prev.borrow_mut().next.replace(Rc::clone(&first)),
+            None => self.head.replace(Rc::clone(&first)),
+        };
+        first.borrow_mut().prev = prev.map(Rc::downgrade);
+        match &next {
+            Some(next) => next.borrow_mut().prev = Some(Rc::downgrade(&last)),
+            None => self.tail = Some(Rc::downgrade(&last)),
+        }
+        last.borrow_mut().next = next;
+
+        self.len += std::mem::replace(&mut other.len, 0);
+        Owner::forward(&mut other.owner, &self.owner);
+    }
+
+    /// A detached node that belongs to this list.
+    fn new_node(&self, value: T) -> Rc<RefCell<Node<T>>> {
+        let mut node = Node::new(value);
+        node.owner = Some(Rc::clone(&self.owner));
+        Rc::new(RefCell::new(node))
+    }
+
+    /// Detaches `node` from its neighbours, joining them to each other.
+    /// The caller keeps the only remaining strong reference to `node`.
+    fn unlink(&mut self, node: &Rc<RefCell<Node<T>>>) {
+        let (prev, next) = {
+            let mut node = node.borrow_mut();
+            (node.prev.take(), node.next.take())
+        };
+        let prev = prev.map(|weak| weak.upgrade().expect("prev node should be alive"));
+
+        match &next {
+            Some(next) => next.borrow_mut().prev = prev.as_ref().map(Rc::downgrade),
+            None => self.tail = prev.as_ref().map(Rc::downgrade),
+        }
+        match prev {
+            Some(prev) => prev.borrow_mut().next = next,
+            None => self.head = next,
+        }
+
+        self.len -= 1;
+    }
+
+    /// Makes a detached node the new head.
+    fn link_front(&mut self, node: Rc<RefCell<Node<T>>>) {
+        match self.head.take() {
+            Some(old_head) => {
+                old_head.borrow_mut().prev = Some(Rc::downgrade(&node));
+                node.borrow_mut().next = Some(old_head);
+            }
+
+            // List is empty — new node is also the tail
+            None => self.tail = Some(Rc::downgrade(&node)),
+        }
+
+        self.head = Some(node);
+        self.len += 1;
+    }
+
+    /// Links a new node holding `value` right after `node` and returns it.
+    fn link_after(&mut self, node: &Rc<RefCell<Node<T>>>, value: T) -> Rc<RefCell<Node<T>>> {
+        let new_node = self.new_node(value);
+        let next = node.borrow_mut().next.take();
+
+        match &next {
+            Some(next) => next.borrow_mut().prev = Some(Rc::downgrade(&new_node)),
+            None => self.tail = Some(Rc::downgrade(&new_node)),
+        }
+        {
+            let mut new = new_node.borrow_mut();
+            new.next = next;
+            new.prev = Some(Rc::downgrade(node));
+        }
+        node.borrow_mut().next = Some(Rc::clone(&new_node));
+
+        self.len += 1;
+        new_node
+    }
+
+    /// Links a new node holding `value` right before `node` and returns it.
+    fn link_before(&mut self, node: &Rc<RefCell<Node<T>>>, value: T) -> Rc<RefCell<Node<T>>> {
+        let new_node = self.new_node(value);
+        let prev = node
+            .borrow_mut()
+            .prev
+            .replace(Rc::downgrade(&new_node))
+            .map(|weak| weak.upgrade().expect("prev node should be alive"));
+
+        {
+            let mut new = new_node.borrow_mut();
+            new.prev = prev.as_ref().map(Rc::downgrade);
+            new.next = Some(Rc::clone(node));
+        }
+        match prev {
+            Some(prev) => prev.borrow_mut().next = Some(Rc::clone(&new_node)),
+            None => self.head = Some(Rc::clone(&new_node)),
+        }
+
+        self.len += 1;
+        new_node
     }
 
     pub fn traverse_forward(&self) -> Vec<T>
     where
         T: Clone,
     {
-        let mut result = Vec::new();
-        let mut curr = self.head.clone();
-
-        while let Some(node) = curr {
-            let node = node.borrow();
-            result.push(node.value.clone());
-            curr = node.next.clone();
-        }
-
-        result
+        self.iter().map(|value| value.borrow().clone()).collect()
     }
 
     pub fn traverse_backward(&self) -> Vec<T>
     where
         T: Clone,
     {
-        let mut result = Vec::new();
-        let mut curr = self.tail.clone();
-
-        while let Some(weak) = curr {
-            if let Some(node) = weak.upgrade() {
-                result.push(node.borrow().value.clone());
-                curr = node.borrow().prev.clone();
-            } else {
-                break;
-            }
-        }
+        self.iter()
+            .rev()
+            .map(|value| value.borrow().clone())
+            .collect()
+    }
+}
+
+/// Takes the value out of a node that has been unlinked from the list.
+fn into_value<T>(node: Rc<RefCell<Node<T>>>) -> T {
+    Rc::try_unwrap(node)
+        .ok()
+        .expect("unlinked node should have no other owners")
+        .into_inner()
+        .value
+}
 
-        result
+// The default drop would free the head, whose `next` frees the following
+// node, and so on, recursing once per element. Break the chain in a loop.
+impl<T> Drop for LinkedList<T> {
+    fn drop(&mut self) {
+        let mut curr = self.head.take();
+        while let Some(node) = curr {
+            curr = node.borrow_mut().next.take();
+        }
     }
 }
 
@@ -99,6 +362,15 @@ mod tests {
 
     use super::*;
 
+    /// Counts its drops, for the tests that check values are freed.
+    struct Counted<'a>(&'a Cell<usize>);
+
+    impl Drop for Counted<'_> {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
     #[test]
     fn test_push_and_traverse_forward() {
         let mut list = LinkedList::new();
@@ -149,4 +421,171 @@ mod tests {
 
         assert_eq!(drop_count.get(), 3);
     }
+
+    #[test]
+    fn test_push_and_pop_both_ends() {
+        let mut list = LinkedList::new();
+        list.push_back(2);
+        list.push_front(1);
+        list.push_back(3);
+        list.push_front(0);
+
+        assert_eq!(list.len(), 4);
+        assert_eq!(list.traverse_forward(), vec![0, 1, 2, 3]);
+        assert_eq!(list.traverse_backward(), vec![3, 2, 1, 0]);
+
+        assert_eq!(list.pop_front(), Some(0));
+        assert_eq!(list.pop_back(), Some(3));
+        assert_eq!(list.traverse_forward(), vec![1, 2]);
+        assert_eq!(list.traverse_backward(), vec![2, 1]);
+
+        assert_eq!(list.pop_back(), Some(2));
+        assert_eq!(list.pop_back(), Some(1));
+        assert_eq!(list.pop_back(), None);
+        assert_eq!(list.pop_front(), None);
+        assert!(list.is_empty());
+        assert!(list.head.is_none() && list.tail.is_none());
+
+        // Still usable after being emptied
+        list.push_front(5);
+        assert_eq!(list.traverse_backward(), vec![5]);
+    }
+
+    #[test]
+    fn test_front_and_back_peek() {
+        let mut list = LinkedList::new();
+        assert!(list.front().is_none());
+        assert!(list.back().is_none());
+
+        list.push_back(String::from("A"));
+        assert_eq!(*list.front().unwrap().borrow(), "A");
+        assert_eq!(*list.back().unwrap().borrow(), "A");
+
+        list.push_back(String::from("B"));
+        list.push_front(String::from("Z"));
+        assert_eq!(*list.front().unwrap().borrow(), "Z");
+        assert_eq!(list.back().unwrap().borrow().len(), 1);
+    }
+
+    #[test]
+    fn test_pop_after_dropping_guard() {
+        let mut list = LinkedList::new();
+        list.push_back(String::from("A"));
+        list.push_back(String::from("B"));
+
+        // The guard must be gone before the list can be modified again
+        let front = list.front().unwrap();
+        assert_eq!(*front.borrow(), "A");
+        drop(front);
+        assert_eq!(list.pop_front().as_deref(), Some("A"));
+
+        let back = list.back().unwrap();
+        assert_eq!(*back.borrow(), "B");
+        drop(back);
+        assert_eq!(list.pop_back().as_deref(), Some("B"));
+    }
+
+    #[test]
+    fn test_popped_values_are_moved_out() {
+        let drop_count = Cell::new(0);
+        let mut list = LinkedList::new();
+        for _ in 0..3 {
+            list.push_back(Counted(&drop_count));
+        }
+
+        // Counted is not Clone: the value itself comes back
+        let front = list.pop_front().unwrap();
+        let back = list.pop_back().unwrap();
+        assert_eq!(drop_count.get(), 0);
+        assert_eq!(list.len(), 1);
+
+        drop(front);
+        drop(back);
+        assert_eq!(drop_count.get(), 2);
+
+        drop(list);
+        assert_eq!(drop_count.get(), 3);
+    }
+
+    #[test]
+    fn test_clear_drops_everything() {
+        let drop_count = Cell::new(0);
+        let mut list = LinkedList::new();
+        for _ in 0..5 {
+            list.push_front(Counted(&drop_count));
+        }
+
+        list.clear();
+        assert_eq!(drop_count.get(), 5);
+        assert!(list.is_empty());
+        assert!(list.front().is_none());
+    }
+
+    #[test]
+    fn test_drop_very_long_list() {
+        let drop_count = Cell::new(0);
+
+        let mut list = LinkedList::new();
+        for _ in 0..1_000_000 {
+            list.push_back(Counted(&drop_count));
+        }
+        drop(list);
+        assert_eq!(drop_count.get(), 1_000_000);
+
+        let list: LinkedList<u64> = (0..1_000_000).collect();
+        let split = {
+            let mut list = list;
+            let mut cursor = list.cursor_front_mut();
+            for _ in 0..500_000 {
+                cursor.move_next();
+            }
+            cursor.split_before()
+        };
+        assert_eq!(split.len(), 500_000);
+    }
+
+    #[test]
+    fn test_append() {
+        let mut list: LinkedList<i32> = (1..=3).collect();
+        let mut other: LinkedList<i32> = (4..=5).collect();
+
+        list.append(&mut other);
+        assert_eq!(list.traverse_forward(), [1, 2, 3, 4, 5]);
+        assert_eq!(list.traverse_backward(), [5, 4, 3, 2, 1]);
+        assert_eq!(list.len(), 5);
+        assert!(other.is_empty() && other.head.is_none() && other.tail.is_none());
+
+        // Appending an empty list, and appending to an empty list
+        list.append(&mut other);
+        other.append(&mut list);
+        assert_eq!(other.traverse_backward(), [5, 4, 3, 2, 1]);
+        assert!(list.is_empty());
+
+        // Both lists stay independently usable
+        other.push_back(6);
+        list.push_back(0);
+        assert_eq!(other.len(), 6);
+        assert_eq!(list.traverse_forward(), [0]);
+    }
+
+    #[test]
+    fn test_split_off() {
+        for at in 0..=6 {
+            let mut list: LinkedList<usize> = (0..6).collect();
+            let rest = list.split_off(at);
+
+            assert_eq!(list.traverse_forward(), (0..at).collect::<Vec<_>>());
+            assert_eq!(rest.traverse_forward(), (at..6).collect::<Vec<_>>());
+            assert_eq!(list.traverse_backward().len(), at);
+            assert_eq!(rest.traverse_backward().len(), 6 - at);
+            assert_eq!((list.len(), rest.len()), (at, 6 - at));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn test_split_off_past_the_end() {
+        let mut list: LinkedList<u8> = (0..3).collect();
+        list.split_off(4);
+    }
 }
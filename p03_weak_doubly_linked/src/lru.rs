@@ -0,0 +1,263 @@
+//! LRU Cache
+//!
+//! A fixed-capacity cache that evicts the least recently used entry. The
+//! entries live in a `LinkedList` ordered from most to least recently used,
+//! and a `HashMap` maps each key to its node's handle. Looking up, promoting
+//! and evicting are all O(1).
+
+use std::{
+    cell::{Ref, RefCell},
+    collections::HashMap,
+    fmt,
+    hash::Hash,
+    marker::PhantomData,
+    rc::Rc,
+};
+
+use crate::{LinkedList, Node, handle::NodeHandle};
+
+type EvictHandler<K, V> = Box<dyn FnMut(&K, &V)>;
+type EntryNode<K, V> = Rc<RefCell<Node<(K, V)>>>;
+
+pub struct LruCache<K, V> {
+    capacity: usize,
+    map: HashMap<K, NodeHandle<(K, V)>>,
+    /// Front is the most recently used entry.
+    list: LinkedList<(K, V)>,
+    on_evict: Option<EvictHandler<K, V>>,
+}
+
+/// Shared access to a cached value, returned by `peek`. Like `ElementRef`,
+/// it keeps the cache borrowed until it is dropped.
+#[derive(Debug)]
+pub struct ValueRef<'a, K, V> {
+    node: EntryNode<K, V>,
+    _cache: PhantomData<&'a LruCache<K, V>>,
+}
+
+impl<K, V> ValueRef<'_, K, V> {
+    pub fn borrow(&self) -> Ref<'_, V> {
+        Ref::map(self.node.borrow(), |node| &node.value.1)
+    }
+}
+
+// Same reason as for `ElementRef`
+impl<K, V> Drop for ValueRef<'_, K, V> {
+    fn drop(&mut self) {}
+}
+
+impl<K: Hash + Eq + Clone, V> LruCache<K, V> {
+    /// Creates an empty cache. Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "LRU capacity must be at least 1");
+
+        LruCache {
+            capacity,
+            map: HashMap::with_capacity(capacity),
+            list: LinkedList::new(),
+            on_evict: None,
+        }
+    }
+
+    /// Sets a callback that sees each entry pushed out by `put`, just before
+    /// it is dropped. Entries taken out explicitly (`pop_lru`, `remove`) are
+    /// not reported.
+    pub fn on_evict<F>(&mut self, handler: F)
+    where
+        F: FnMut(&K, &V) + 'static,
+    {
+        self.on_evict = Some(Box::new(handler));
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.map.contains_key(key)
+    }
+
+    /// Returns the value and marks it as most recently used.
+    pub fn get(&mut self, key: &K) -> Option<Ref<'_, V>> {
+        let handle = self.map.get(key)?;
+        self.list
+            .move_to_front(handle)
+            .expect("cached node should be alive");
+
+        // The entry is now the head, the one node the list owns directly
+        let head = self.list.head.as_ref()?;
+        Some(Ref::map(head.borrow(), |node| &node.value.1))
+    }
+
+    /// Returns the value without changing the usage order.
+    pub fn peek(&self, key: &K) -> Option<ValueRef<'_, K, V>> {
+        Some(ValueRef {
+            node: self.node(key)?,
+            _cache: PhantomData,
+        })
+    }
+
+    /// Inserts or updates an entry and marks it as most recently used.
+    /// Returns the previous value for `key`, if any. When a new key does not
+    /// fit, the least recently used entry is evicted first.
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(node) = self.node(&key) {
+            let old = std::mem::replace(&mut node.borrow_mut().value.1, value);
+            self.list
+                .move_to_front(&self.map[&key])
+                .expect("cached node should be alive");
+            return Some(old);
+        }
+
+        if self.list.len() == self.capacity
+            && let Some((old_key, old_value)) = self.pop_lru()
+            && let Some(on_evict) = &mut self.on_evict
+        {
+            on_evict(&old_key, &old_value);
+        }
+
+        let handle = self.list.push_front((key.clone(), value));
+        self.map.insert(key, handle);
+        None
+    }
+
+    /// Removes and returns the least recently used entry.
+    pub fn pop_lru(&mut self) -> Option<(K, V)> {
+        let (key, value) = self.list.pop_back()?;
+        self.map.remove(&key);
+        Some((key, value))
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let handle = self.map.remove(key)?;
+        let (_, value) = self
+            .list
+            .remove(&handle)
+            .expect("cached node should be alive");
+        Some(value)
+    }
+
+    /// Keys from most to least recently used.
+    pub fn keys(&self) -> Vec<K> {
+        self.list
+            .iter()
+            .map(|entry| entry.borrow().0.clone())
+            .collect()
+    }
+
+    fn node(&self, key: &K) -> Option<EntryNode<K, V>> {
+        let handle = self.map.get(key)?;
+        Some(handle.upgrade().expect("cached node should be alive"))
+    }
+}
+
+impl<K, V> fmt::Debug for LruCache<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LruCache")
+            .field("capacity", &self.capacity)
+            .field("len", &self.list.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    #[test]
+    fn test_get_promotes_and_put_evicts_lru() {
+        let mut cache = LruCache::new(3);
+        cache.put("a", 1);
+        cache.put("b", 2);
+        cache.put("c", 3);
+        assert_eq!(cache.keys(), ["c", "b", "a"]);
+
+        assert_eq!(*cache.get(&"a").unwrap(), 1);
+        assert_eq!(cache.keys(), ["a", "c", "b"]);
+
+        // "b" is now the least recently used
+        cache.put("d", 4);
+        assert!(!cache.contains_key(&"b"));
+        assert!(cache.get(&"b").is_none());
+        assert_eq!(cache.keys(), ["d", "a", "c"]);
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[test]
+    fn test_put_existing_key_updates_in_place() {
+        let mut cache = LruCache::new(2);
+        assert_eq!(cache.put(1, String::from("one")), None);
+        cache.put(2, String::from("two"));
+
+        assert_eq!(cache.put(1, String::from("uno")).as_deref(), Some("one"));
+        assert_eq!(cache.keys(), [1, 2]);
+        assert_eq!(cache.len(), 2);
+
+        cache.put(3, String::from("three"));
+        assert_eq!(cache.keys(), [3, 1]);
+        assert_eq!(cache.get(&1).as_deref().map(String::as_str), Some("uno"));
+    }
+
+    #[test]
+    fn test_peek_does_not_promote() {
+        let mut cache = LruCache::new(2);
+        cache.put('x', 10);
+        cache.put('y', 20);
+
+        assert_eq!(*cache.peek(&'x').unwrap().borrow(), 10);
+        assert!(cache.peek(&'z').is_none());
+        assert_eq!(cache.keys(), ['y', 'x']);
+
+        assert_eq!(cache.pop_lru(), Some(('x', 10)));
+        assert_eq!(cache.remove(&'y'), Some(20));
+        assert_eq!(cache.pop_lru(), None);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_evicted_values_are_dropped_and_reported() {
+        struct DropCounter(Rc<Cell<usize>>);
+
+        impl Drop for DropCounter {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let drops = Rc::new(Cell::new(0));
+        let evicted = Rc::new(RefCell::new(Vec::new()));
+
+        let mut cache = LruCache::new(2);
+        let log = Rc::clone(&evicted);
+        cache.on_evict(move |key: &u32, _: &DropCounter| log.borrow_mut().push(*key));
+
+        for key in 0..5 {
+            cache.put(key, DropCounter(Rc::clone(&drops)));
+        }
+        assert_eq!(*evicted.borrow(), [0, 1, 2]);
+        assert_eq!(drops.get(), 3);
+
+        // Peeking works without `Clone`
+        let peeked = cache.peek(&3).unwrap();
+        assert!(Rc::ptr_eq(&peeked.borrow().0, &drops));
+        drop(peeked);
+
+        // Replacing a value drops the old one once the caller lets go of it
+        drop(cache.put(4, DropCounter(Rc::clone(&drops))));
+        assert_eq!(drops.get(), 4);
+
+        drop(cache);
+        assert_eq!(drops.get(), 6);
+        assert_eq!(evicted.borrow().len(), 3);
+    }
+}
@@ -0,0 +1,288 @@
+//! Sorting
+//!
+//! A stable merge sort that relinks the existing nodes instead of moving
+//! values around, so nothing is cloned and node handles stay valid. It works
+//! bottom-up without recursion: sorted runs of 1, 2, 4, ... nodes are kept
+//! in a small table (like a binary counter) and merged in loops, which keeps
+//! it stack-safe for any length. The merges only follow `next`; the `Weak`
+//! prev links and `tail` are rebuilt in one pass at the end.
+//!
+//! While sorting, every node sits in one of the detached chains of a
+//! `Sorter`. Its `Drop` links them all back into the list, so if `compare`
+//! panics the list keeps all its elements, just not in any particular order.
+
+use std::{cell::RefCell, cmp::Ordering, rc::Rc};
+
+use crate::{LinkedList, Node};
+
+type NodeRc<T> = Rc<RefCell<Node<T>>>;
+
+/// A sorted, non-empty chain linked through `next`, and its last node.
+struct Run<T> {
+    head: NodeRc<T>,
+    tail: NodeRc<T>,
+}
+
+/// The sort in progress. Between steps each node is in exactly one chain.
+struct Sorter<'a, T> {
+    list: &'a mut LinkedList<T>,
+    /// Nodes not looked at yet.
+    rest: Option<NodeRc<T>>,
+    /// `runs[i]` holds 2^i nodes or nothing; higher slots hold earlier nodes.
+    runs: Vec<Option<Run<T>>>,
+    /// The two inputs of the current merge, and what it has merged so far.
+    left: Option<NodeRc<T>>,
+    right: Option<NodeRc<T>>,
+    merged: Option<NodeRc<T>>,
+}
+
+impl<T> LinkedList<T> {
+    /// Sorts the list with a comparator, keeping equal elements in order.
+    /// O(n log n) time and O(log n) extra space.
+    ///
+    /// If `compare` panics, the list still holds all its elements, but in
+    /// an unspecified order.
+    pub fn sort_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        self.tail = None;
+        let mut sorter = Sorter {
+            rest: self.head.take(),
+            list: self,
+            runs: Vec::new(),
+            left: None,
+            right: None,
+            merged: None,
+        };
+
+        while let Some(node) = sorter.rest.take() {
+            sorter.rest = node.borrow_mut().next.take();
+            let mut carry = Run {
+                head: Rc::clone(&node),
+                tail: node,
+            };
+
+            let mut level = 0;
+            while let Some(earlier) = sorter.runs.get_mut(level).and_then(Option::take) {
+                carry = sorter.merge(earlier, carry, &mut compare);
+                level += 1;
+            }
+            match sorter.runs.get_mut(level) {
+                Some(slot) => *slot = Some(carry),
+                None => sorter.runs.push(Some(carry)),
+            }
+        }
+
+        // Lower slots hold later nodes, so they go on the right
+        let mut sorted: Option<Run<T>> = None;
+        for level in 0..sorter.runs.len() {
+            let Some(earlier) = sorter.runs[level].take() else {
+                continue;
+            };
+            sorted = Some(match sorted.take() {
+                Some(later) => sorter.merge(earlier, later, &mut compare),
+                None => earlier,
+            });
+        }
+
+        // Dropping the sorter links the result back in
+        sorter.merged = sorted.map(|run| run.head);
+    }
+
+    /// Sorts the list by a key, keeping equal elements in order.
+    pub fn sort_by_key<K, F>(&mut self, mut key: F)
+    where
+        K: Ord,
+        F: FnMut(&T) -> K,
+    {
+        self.sort_by(|a, b| key(a).cmp(&key(b)));
+    }
+}
+
+impl<T> Sorter<'_, T> {
+    /// Merges two sorted runs. On ties the node from `earlier` goes first,
+    /// which is what makes the sort stable.
+    fn merge<F>(&mut self, earlier: Run<T>, later: Run<T>, compare: &mut F) -> Run<T>
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        self.left = Some(earlier.head);
+        self.right = Some(later.head);
+        let mut tail: Option<NodeRc<T>> = None;
+
+        while let (Some(l), Some(r)) = (&self.left, &self.right) {
+            let source = if compare(&r.borrow().value, &l.borrow().value) == Ordering::Less {
+                &mut self.right
+            } else {
+                &mut self.left
+            };
+            let node = source.take().expect("both runs are non-empty here");
+            *source = node.borrow_mut().next.take();
+
+            match &tail {
+                Some(tail) => tail.borrow_mut().next = Some(Rc::clone(&node)),
+                None => self.merged = Some(Rc::clone(&node)),
+            }
+            tail = Some(node);
+        }
+
+        // One run is used up; the rest of the other is already sorted
+        let (rest, rest_tail) = match self.left.take() {
+            Some(left) => (left, earlier.tail),
+            None => (self.right.take().expect("one run is left"), later.tail),
+        };
+        match tail {
+            Some(tail) => tail.borrow_mut().next = Some(rest),
+            None => self.merged = Some(rest),
+        }
+
+        Run {
+            head: self.merged.take().expect("merged run is non-empty"),
+            tail: rest_tail,
+        }
+    }
+}
+
+// Chains the pieces back together and rebuilds `prev`, `tail` and `len` in
+// one pass. After a finished sort only `merged` is left.
+impl<T> Drop for Sorter<'_, T> {
+    fn drop(&mut self) {
+        let chains = [
+            self.merged.take(),
+            self.left.take(),
+            self.right.take(),
+            self.rest.take(),
+        ]
+        .into_iter()
+        .chain(self.runs.drain(..).map(|run| run.map(|run| run.head)))
+        .flatten();
+
+        let mut len = 0;
+        let mut prev: Option<NodeRc<T>> = None;
+        for head in chains {
+            match &prev {
+                Some(prev) => prev.borrow_mut().next = Some(Rc::clone(&head)),
+                None => self.list.head = Some(Rc::clone(&head)),
+            }
+
+            let mut curr = Some(head);
+            while let Some(node) = curr {
+                let mut node_ref = node.borrow_mut();
+                node_ref.prev = prev.as_ref().map(Rc::downgrade);
+                curr = node_ref.next.clone();
+                drop(node_ref);
+                len += 1;
+                prev = Some(node);
+            }
+        }
+
+        self.list.tail = prev.as_ref().map(Rc::downgrade);
+        self.list.len = len;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{self, AssertUnwindSafe};
+
+    use super::*;
+    use crate::test_util::list_of;
+
+    #[test]
+    fn test_sort_small_lists() {
+        let mut list: LinkedList<i32> = LinkedList::new();
+        list.sort_by(|a, b| a.cmp(b));
+        assert!(list.is_empty());
+        assert_eq!(list.validate(), Ok(()));
+
+        let mut list = list_of(&[5, 3, 9, 1, 3, 7, 2]);
+        list.sort_by(|a, b| a.cmp(b));
+        assert_eq!(list.traverse_forward(), [1, 2, 3, 3, 5, 7, 9]);
+        assert_eq!(list.traverse_backward(), [9, 7, 5, 3, 3, 2, 1]);
+        assert_eq!(list.validate(), Ok(()));
+
+        list.sort_by(|a, b| b.cmp(a));
+        assert_eq!(list.traverse_forward(), [9, 7, 5, 3, 3, 2, 1]);
+        assert_eq!(*list.back().unwrap().borrow(), 1);
+        assert_eq!(list.pop_back(), Some(1));
+    }
+
+    #[test]
+    fn test_sort_is_stable() {
+        let mut list: LinkedList<(u32, char)> = [(2, 'a'), (1, 'b'), (2, 'c'), (0, 'd'), (1, 'e')]
+            .into_iter()
+            .collect();
+
+        list.sort_by_key(|&(key, _)| key);
+        assert_eq!(
+            list.traverse_forward(),
+            [(0, 'd'), (1, 'b'), (1, 'e'), (2, 'a'), (2, 'c')]
+        );
+
+        // Sizes that are not a power of two exercise the final merges too
+        let mut list: LinkedList<(u32, u32)> = (0..1_000).map(|i| (i * 7 % 13, i)).collect();
+        list.sort_by_key(|&(key, _)| key);
+        let sorted = list.traverse_forward();
+        assert!(sorted.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_sort_relinks_nodes_without_cloning() {
+        // Not Clone: the values must stay where they are
+        #[derive(Debug, PartialEq)]
+        struct Item(u32);
+
+        let mut list = LinkedList::new();
+        let handles: Vec<_> = [3, 1, 2]
+            .into_iter()
+            .map(|i| list.push_back(Item(i)))
+            .collect();
+
+        list.sort_by_key(|item| item.0);
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(list.front().unwrap().borrow().0, 1);
+
+        // The same nodes were relinked, so the handles still point at them
+        assert_eq!(list.remove(&handles[0]), Ok(Item(3)));
+        assert_eq!(list.remove(&handles[1]), Ok(Item(1)));
+        assert_eq!(list.pop_front(), Some(Item(2)));
+    }
+
+    #[test]
+    fn test_sort_very_long_list() {
+        let mut list: LinkedList<u32> = (0..1_000_000).rev().collect();
+        list.sort_by(|a, b| a.cmp(b));
+
+        assert_eq!(list.len(), 1_000_000);
+        assert_eq!(*list.front().unwrap().borrow(), 0);
+        assert_eq!(*list.back().unwrap().borrow(), 999_999);
+        assert!(list.iter().map(|value| *value.borrow()).eq(0..1_000_000));
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_panicking_compare_on_long_list() {
+        let mut list: LinkedList<u32> = (0..1_000_000).rev().collect();
+        let mut calls = 0;
+
+        // Panics halfway through the merges, with nodes spread over many
+        // runs; they must all end up back in the list
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            list.sort_by(|a, b| {
+                calls += 1;
+                assert!(calls < 1_500_000, "comparator gave up");
+                a.cmp(b)
+            });
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(list.len(), 1_000_000);
+        assert_eq!(list.validate(), Ok(()));
+
+        let mut values = list.traverse_forward();
+        values.sort_unstable();
+        assert!(values.into_iter().eq(0..1_000_000));
+    }
+}
@@ -0,0 +1,603 @@
+//! Thread-Safe Doubly-Linked List
+//!
+//! The same shape as `LinkedList` (strong `next`, `Weak` prev), but every
+//! node sits in its own `Mutex`, so threads working on different parts of
+//! the list don't block each other. Two sentinel nodes mark the ends, so
+//! every real node always has a node before and after it.
+//!
+//! Forward walks use hand-over-hand locking (lock coupling): the next node
+//! is locked before the current one is released, so the walker can never
+//! be overtaken or lose its place.
+//!
+//! # Lock order
+//!
+//! A thread only ever *waits* for a node's lock while holding locks on
+//! nodes before it, never after it. Forward walkers follow this naturally.
+//! Backward moves never hold two locks: a node's `prev` is read under the
+//! node's own lock, which is enough, because nothing can be linked in or
+//! unlinked before a node without locking it. When a backward operation
+//! needs two neighbours locked at once (`push_back`, `pop_back`), it
+//! releases everything, locks them again front to back, and checks that
+//! they are still neighbours, retrying otherwise. Since all waiting happens
+//! in one direction, there is no cycle of threads waiting on each other.
+
+use std::{
+    fmt,
+    ops::ControlFlow,
+    sync::{
+        Arc, Mutex, MutexGuard, PoisonError, Weak,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+type Link<T> = Arc<Mutex<SyncNode<T>>>;
+
+struct SyncNode<T> {
+    /// `None` for the sentinels, and for a node whose value was taken out.
+    value: Option<T>,
+    next: Option<Link<T>>,
+    prev: Weak<Mutex<SyncNode<T>>>,
+    /// Set when the node is unlinked: its predecessor at that moment.
+    /// A backward walker standing on the node resumes from there.
+    unlinked_from: Option<Link<T>>,
+}
+
+impl<T> SyncNode<T> {
+    fn sentinel() -> Link<T> {
+        Arc::new(Mutex::new(SyncNode {
+            value: None,
+            next: None,
+            prev: Weak::new(),
+            unlinked_from: None,
+        }))
+    }
+
+    fn next_is(&self, node: &Link<T>) -> bool {
+        self.next
+            .as_ref()
+            .is_some_and(|next| Arc::ptr_eq(next, node))
+    }
+}
+
+/// A node together with its held lock.
+struct Locked<'a, T> {
+    link: &'a Link<T>,
+    node: &'a mut SyncNode<T>,
+}
+
+fn lock<T>(link: &Link<T>) -> MutexGuard<'_, SyncNode<T>> {
+    link.lock().unwrap()
+}
+
+/// The node before `curr`, read under `curr`'s lock alone. If `curr` has
+/// been unlinked, this is the node it was unlinked from.
+fn step_back<T>(curr: &Link<T>) -> Link<T> {
+    let guard = lock(curr);
+    match &guard.unlinked_from {
+        Some(from) => Arc::clone(from),
+        None => guard.prev.upgrade().expect("linked node's prev is alive"),
+    }
+}
+
+/// Links a new node between two locked neighbours.
+fn link_between<T>(pred: Locked<'_, T>, next: Locked<'_, T>, value: T) {
+    let node = Arc::new(Mutex::new(SyncNode {
+        value: Some(value),
+        next: Some(Arc::clone(next.link)),
+        prev: Arc::downgrade(pred.link),
+        unlinked_from: None,
+    }));
+
+    next.node.prev = Arc::downgrade(&node);
+    pred.node.next = Some(node);
+}
+
+/// Unlinks a locked node from its locked neighbours and takes its value.
+fn unlink<T>(pred: Locked<'_, T>, node: Locked<'_, T>, next: Locked<'_, T>) -> T {
+    next.node.prev = Arc::downgrade(pred.link);
+    pred.node.next = Some(Arc::clone(next.link));
+
+    node.node.next = None;
+    node.node.unlinked_from = Some(Arc::clone(pred.link));
+    node.node
+        .value
+        .take()
+        .expect("only sentinels have no value")
+}
+
+pub struct SyncList<T> {
+    head: Link<T>,
+    tail: Link<T>,
+    len: AtomicUsize,
+}
+
+impl<T> SyncList<T> {
+    pub fn new() -> Self {
+        let head = SyncNode::sentinel();
+        let tail = SyncNode::sentinel();
+        lock(&head).next = Some(Arc::clone(&tail));
+        lock(&tail).prev = Arc::downgrade(&head);
+
+        SyncList {
+            head,
+            tail,
+            len: AtomicUsize::new(0),
+        }
+    }
+
+    /// Number of elements. While other threads modify the list this is
+    /// only a snapshot.
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::SeqCst)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn push_front(&self, value: T) {
+        let mut head = lock(&self.head);
+        let first = head.next.clone().expect("head sentinel has a next");
+        let mut first_guard = lock(&first);
+
+        link_between(
+            Locked {
+                link: &self.head,
+                node: &mut head,
+            },
+            Locked {
+                link: &first,
+                node: &mut first_guard,
+            },
+            value,
+        );
+        self.len.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn push_back(&self, value: T) {
+        loop {
+            let last = step_back(&self.tail);
+            let mut last_guard = lock(&last);
+            let mut tail_guard = lock(&self.tail);
+
+            // Something was linked in or out at the end meanwhile
+            if !last_guard.next_is(&self.tail) {
+                continue;
+            }
+
+            link_between(
+                Locked {
+                    link: &last,
+                    node: &mut last_guard,
+                },
+                Locked {
+                    link: &self.tail,
+                    node: &mut tail_guard,
+                },
+                value,
+            );
+            self.len.fetch_add(1, Ordering::SeqCst);
+            return;
+        }
+    }
+
+    pub fn pop_front(&self) -> Option<T> {
+        let mut head = lock(&self.head);
+        let first = head.next.clone().expect("head sentinel has a next");
+        if Arc::ptr_eq(&first, &self.tail) {
+            return None;
+        }
+
+        let mut first_guard = lock(&first);
+        let second = first_guard.next.clone().expect("linked node has a next");
+        let mut second_guard = lock(&second);
+
+        let value = unlink(
+            Locked {
+                link: &self.head,
+                node: &mut head,
+            },
+            Locked {
+                link: &first,
+                node: &mut first_guard,
+            },
+            Locked {
+                link: &second,
+                node: &mut second_guard,
+            },
+        );
+        self.len.fetch_sub(1, Ordering::SeqCst);
+        Some(value)
+    }
+
+    pub fn pop_back(&self) -> Option<T> {
+        loop {
+            let last = step_back(&self.tail);
+            if Arc::ptr_eq(&last, &self.head) {
+                if lock(&self.head).next_is(&self.tail) {
+                    return None;
+                }
+                continue;
+            }
+
+            let before = step_back(&last);
+            let mut before_guard = lock(&before);
+            let mut last_guard = lock(&last);
+            let mut tail_guard = lock(&self.tail);
+
+            if !before_guard.next_is(&last) || !last_guard.next_is(&self.tail) {
+                continue;
+            }
+
+            let value = unlink(
+                Locked {
+                    link: &before,
+                    node: &mut before_guard,
+                },
+                Locked {
+                    link: &last,
+                    node: &mut last_guard,
+                },
+                Locked {
+                    link: &self.tail,
+                    node: &mut tail_guard,
+                },
+            );
+            self.len.fetch_sub(1, Ordering::SeqCst);
+            return Some(value);
+        }
+    }
+
+    /// Inserts before the first greater element, keeping a sorted list sorted.
+    pub fn insert_sorted(&self, value: T)
+    where
+        T: Ord,
+    {
+        let mut value = Some(value);
+
+        self.walk(|pred, curr| {
+            let goes_here = match (&curr.node.value, &value) {
+                (Some(existing), Some(value)) => existing > value,
+                // Reached the tail sentinel
+                _ => true,
+            };
+            if !goes_here {
+                return ControlFlow::Continue(());
+            }
+
+            link_between(pred, curr, value.take().expect("inserted once"));
+            ControlFlow::Break(())
+        });
+        self.len.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Removes and returns the first element that matches.
+    pub fn remove_first<F>(&self, mut matches: F) -> Option<T>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let mut removed = None;
+
+        self.walk(|pred, curr| {
+            if !curr.node.value.as_ref().is_some_and(&mut matches) {
+                return ControlFlow::Continue(());
+            }
+
+            let next = curr.node.next.clone().expect("linked node has a next");
+            let mut next_guard = lock(&next);
+            removed = Some(unlink(
+                pred,
+                curr,
+                Locked {
+                    link: &next,
+                    node: &mut next_guard,
+                },
+            ));
+            ControlFlow::Break(())
+        });
+
+        if removed.is_some() {
+            self.len.fetch_sub(1, Ordering::SeqCst);
+        }
+        removed
+    }
+
+    pub fn contains(&self, value: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        let mut found = false;
+        self.walk(|_, curr| {
+            found = curr.node.value.as_ref() == Some(value);
+            if found {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        found
+    }
+
+    /// Copies the elements front to back.
+    pub fn to_vec(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let mut values = Vec::new();
+        self.walk(|_, curr| {
+            values.extend(curr.node.value.clone());
+            ControlFlow::Continue(())
+        });
+        values
+    }
+
+    /// Copies the elements back to front.
+    pub fn to_vec_rev(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let mut values = Vec::new();
+        let mut curr = Arc::clone(&self.tail);
+
+        loop {
+            let prev = step_back(&curr);
+            if Arc::ptr_eq(&prev, &self.head) {
+                return values;
+            }
+
+            // Empty if `prev` was unlinked after the step
+            values.extend(lock(&prev).value.clone());
+            curr = prev;
+        }
+    }
+
+    /// Hand-over-hand walk from the head sentinel. `visit` gets each pair of
+    /// neighbours (the second may be the tail sentinel) with both locked.
+    fn walk<F>(&self, mut visit: F)
+    where
+        F: FnMut(Locked<'_, T>, Locked<'_, T>) -> ControlFlow<()>,
+    {
+        // Two slots take turns being the node behind and the node ahead, so
+        // each lock is released only after the next one is held
+        let mut slot_a = Arc::clone(&self.head);
+        let mut guard_a = lock(&slot_a);
+        let mut slot_b;
+
+        loop {
+            let Some(next) = guard_a.next.clone() else {
+                return;
+            };
+            slot_b = next;
+            let mut guard_b = lock(&slot_b);
+            let behind = Locked {
+                link: &slot_a,
+                node: &mut guard_a,
+            };
+            let ahead = Locked {
+                link: &slot_b,
+                node: &mut guard_b,
+            };
+            if visit(behind, ahead).is_break() {
+                return;
+            }
+            drop(guard_a);
+
+            let Some(next) = guard_b.next.clone() else {
+                return;
+            };
+            slot_a = next;
+            guard_a = lock(&slot_a);
+            let behind = Locked {
+                link: &slot_b,
+                node: &mut guard_b,
+            };
+            let ahead = Locked {
+                link: &slot_a,
+                node: &mut guard_a,
+            };
+            if visit(behind, ahead).is_break() {
+                return;
+            }
+            drop(guard_b);
+        }
+    }
+}
+
+impl<T> Default for SyncList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for SyncList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SyncList")
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+// Break the chain in a loop, as for `LinkedList`
+impl<T> Drop for SyncList<T> {
+    fn drop(&mut self) {
+        let mut curr = self
+            .head
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .next
+            .take();
+        while let Some(node) = curr {
+            curr = node
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .next
+                .take();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, thread};
+
+    use super::*;
+
+    /// Checks that both directions agree and returns the contents.
+    fn contents(list: &SyncList<u32>) -> Vec<u32> {
+        let forward = list.to_vec();
+        let mut backward = list.to_vec_rev();
+        backward.reverse();
+
+        assert_eq!(forward, backward);
+        assert_eq!(forward.len(), list.len());
+        forward
+    }
+
+    #[test]
+    fn test_single_threaded_operations() {
+        let list = SyncList::new();
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.pop_back(), None);
+
+        list.push_back(2);
+        list.push_front(1);
+        list.push_back(4);
+        list.insert_sorted(3);
+        list.insert_sorted(0);
+        list.insert_sorted(9);
+        assert_eq!(contents(&list), [0, 1, 2, 3, 4, 9]);
+
+        assert!(list.contains(&3));
+        assert!(!list.contains(&5));
+        assert_eq!(list.remove_first(|&value| value % 2 == 1), Some(1));
+        assert_eq!(list.remove_first(|&value| value > 100), None);
+
+        assert_eq!(list.pop_front(), Some(0));
+        assert_eq!(list.pop_back(), Some(9));
+        assert_eq!(contents(&list), [2, 3, 4]);
+    }
+
+    #[test]
+    fn test_concurrent_pushes_at_both_ends() {
+        let list = SyncList::new();
+
+        thread::scope(|scope| {
+            for thread_id in 0..8 {
+                let list = &list;
+                scope.spawn(move || {
+                    for i in 0..500 {
+                        let value = thread_id * 1_000 + i;
+                        if value % 2 == 0 {
+                            list.push_back(value);
+                        } else {
+                            list.push_front(value);
+                        }
+                    }
+                });
+            }
+        });
+
+        let mut values = contents(&list);
+        assert_eq!(values.len(), 4_000);
+        values.sort_unstable();
+        values.dedup();
+        assert_eq!(values.len(), 4_000);
+    }
+
+    #[test]
+    fn test_concurrent_sorted_inserts() {
+        let list = SyncList::new();
+
+        thread::scope(|scope| {
+            for thread_id in 0..4 {
+                let list = &list;
+                scope.spawn(move || {
+                    for i in 0..200 {
+                        // Interleaved values, so threads insert all over the list
+                        list.insert_sorted(i * 4 + thread_id);
+                    }
+                });
+            }
+        });
+
+        assert_eq!(contents(&list), (0..800).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_mixed_stress_with_walkers_in_both_directions() {
+        let list = SyncList::new();
+        let producers = 4;
+        let per_producer = 2_000;
+
+        let consumed: Vec<Vec<u32>> = thread::scope(|scope| {
+            for producer in 0..producers {
+                let list = &list;
+                scope.spawn(move || {
+                    for i in 0..per_producer {
+                        let value = producer * per_producer + i;
+                        match i % 3 {
+                            0 => list.push_back(value),
+                            1 => list.push_front(value),
+                            _ => list.insert_sorted(value),
+                        }
+                    }
+                });
+            }
+
+            // Walkers in both directions, crossing producers and consumers
+            for _ in 0..2 {
+                let list = &list;
+                scope.spawn(move || {
+                    for _ in 0..50 {
+                        let forward = list.to_vec();
+                        let backward = list.to_vec_rev();
+                        assert_eq!(forward.iter().collect::<HashSet<_>>().len(), forward.len());
+                        assert_eq!(
+                            backward.iter().collect::<HashSet<_>>().len(),
+                            backward.len()
+                        );
+                        list.contains(&0);
+                    }
+                });
+            }
+
+            let consumers: Vec<_> = (0..4)
+                .map(|consumer| {
+                    let list = &list;
+                    scope.spawn(move || {
+                        let mut taken = Vec::new();
+                        for round in 0..1_500 {
+                            let value = match (consumer + round) % 3 {
+                                0 => list.pop_front(),
+                                1 => list.pop_back(),
+                                _ => list.remove_first(|value| value % 7 == 0),
+                            };
+                            taken.extend(value);
+                        }
+                        taken
+                    })
+                })
+                .collect();
+
+            consumers
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+
+        // Every value ended up exactly once: taken by a consumer or still listed
+        let mut all: Vec<u32> = consumed.into_iter().flatten().collect();
+        all.extend(contents(&list));
+        all.sort_unstable();
+        assert_eq!(all, (0..producers * per_producer).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_drop_very_long_list() {
+        let list = SyncList::new();
+        for value in 0..1_000_000 {
+            list.push_front(value);
+        }
+        drop(list);
+    }
+}
@@ -0,0 +1,25 @@
+//! Test Helpers
+//!
+//! Shared by the unit tests of this crate.
+
+use crate::LinkedList;
+
+pub fn list_of(values: &[i32]) -> LinkedList<i32> {
+    values.iter().copied().collect()
+}
+
+/// Deterministic xorshift numbers for the randomized tests.
+pub struct Rng(pub u64);
+
+impl Rng {
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+}
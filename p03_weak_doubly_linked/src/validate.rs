@@ -0,0 +1,343 @@
+//! Invariant Checking
+//!
+//! `validate` walks a list and checks the structure the rest of the crate
+//! relies on: every node's `prev` points back at its predecessor, `tail`
+//! points at the last node, the chain ends, `len` matches, each node is
+//! labelled with this list, and each node is owned by exactly one strong
+//! reference (`head` or its predecessor's `next`). Meant for tests and
+//! debugging; it costs O(n) time and memory.
+
+use std::{collections::HashMap, error::Error, fmt, rc::Rc};
+
+use crate::LinkedList;
+
+/// The first broken invariant found. Indexes count from the head.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListError {
+    /// The head node has a `prev` link.
+    HeadHasPrev,
+    /// The node's `prev` is missing, dangling, or not its predecessor.
+    BrokenPrev { index: usize },
+    /// The node's `next` leads back to the node at `target`.
+    Cycle { index: usize, target: usize },
+    /// The node is labelled with a different list, so handles to it would
+    /// be rejected.
+    ForeignNode { index: usize },
+    /// `tail` is not the last node reachable from `head`
+    /// (`last_index` is `None` when no node is).
+    TailMismatch { last_index: Option<usize> },
+    /// `len` disagrees with the number of reachable nodes.
+    LengthMismatch { expected: usize, found: usize },
+    /// The node has `owners` strong references instead of exactly one.
+    UnexpectedStrongCount { index: usize, owners: usize },
+}
+
+impl fmt::Display for ListError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListError::HeadHasPrev => write!(f, "head node has a prev link"),
+            ListError::BrokenPrev { index } => {
+                write!(f, "node {} does not link back to its predecessor", index)
+            }
+            ListError::Cycle { index, target } => {
+                write!(f, "node {} links forward to node {}", index, target)
+            }
+            ListError::ForeignNode { index } => {
+                write!(f, "node {} belongs to another list", index)
+            }
+            ListError::TailMismatch {
+                last_index: Some(last),
+            } => write!(f, "tail is not the last node, node {}", last),
+            ListError::TailMismatch { last_index: None } => {
+                write!(f, "tail is set but the list has no nodes")
+            }
+            ListError::LengthMismatch { expected, found } => {
+                write!(f, "length is {} but {} nodes are linked", expected, found)
+            }
+            ListError::UnexpectedStrongCount { index, owners } => {
+                write!(
+                    f,
+                    "node {} has {} strong owners instead of 1",
+                    index, owners
+                )
+            }
+        }
+    }
+}
+
+impl Error for ListError {}
+
+impl<T> LinkedList<T> {
+    /// Checks every structural invariant.
+    ///
+    /// Element guards and cursors hold extra strong references, so call this
+    /// while none are alive.
+    pub fn validate(&self) -> Result<(), ListError> {
+        let mut seen = HashMap::new();
+        let mut prev = None;
+        let mut curr = self.head.clone();
+        let mut index = 0;
+        // Reported only once the structure is known to be sound, since a
+        // cycle or stray link also shows up as an extra owner
+        let mut count_error = None;
+
+        while let Some(node) = curr {
+            if let Some(&target) = seen.get(&Rc::as_ptr(&node)) {
+                return Err(ListError::Cycle {
+                    index: index - 1,
+                    target,
+                });
+            }
+            seen.insert(Rc::as_ptr(&node), index);
+
+            // One owner plus the clone held by this walk
+            let owners = Rc::strong_count(&node) - 1;
+            if owners != 1 && count_error.is_none() {
+                count_error = Some(ListError::UnexpectedStrongCount { index, owners });
+            }
+
+            let node_ref = node.borrow();
+            if !self.labels(&node_ref) {
+                return Err(ListError::ForeignNode { index });
+            }
+
+            match (&node_ref.prev, &prev) {
+                (None, None) => {}
+                (Some(_), None) => return Err(ListError::HeadHasPrev),
+                (None, Some(_)) => return Err(ListError::BrokenPrev { index }),
+                (Some(weak), Some(prev)) => {
+                    if !weak.upgrade().is_some_and(|back| Rc::ptr_eq(&back, prev)) {
+                        return Err(ListError::BrokenPrev { index });
+                    }
+                }
+            }
+
+            curr = node_ref.next.clone();
+            drop(node_ref);
+            prev = Some(node);
+            index += 1;
+        }
+
+        let last_index = index.checked_sub(1);
+        let tail = self.tail.as_ref().map(|weak| weak.upgrade());
+        let tail_ok = match (tail, &prev) {
+            (None, None) => true,
+            (Some(Some(tail)), Some(last)) => Rc::ptr_eq(&tail, last),
+            _ => false,
+        };
+        if !tail_ok {
+            return Err(ListError::TailMismatch { last_index });
+        }
+
+        if index != self.len {
+            return Err(ListError::LengthMismatch {
+                expected: self.len,
+                found: index,
+            });
+        }
+
+        count_error.map_or(Ok(()), Err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::*;
+    use crate::{
+        handle::NodeHandle,
+        test_util::{Rng, list_of},
+    };
+
+    #[test]
+    fn test_valid_lists() {
+        assert_eq!(LinkedList::<i32>::new().validate(), Ok(()));
+        assert_eq!(list_of(&[1]).validate(), Ok(()));
+        assert_eq!(list_of(&[1, 2, 3]).validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_detects_broken_links() {
+        let list = list_of(&[1, 2, 3]);
+        let head = list.head.clone().unwrap();
+        let second = head.borrow().next.clone().unwrap();
+        let third = second.borrow().next.clone().unwrap();
+
+        head.borrow_mut().prev = Some(Rc::downgrade(&third));
+        assert_eq!(list.validate(), Err(ListError::HeadHasPrev));
+        head.borrow_mut().prev = None;
+
+        let saved = third.borrow_mut().prev.replace(Rc::downgrade(&third));
+        assert_eq!(list.validate(), Err(ListError::BrokenPrev { index: 2 }));
+        third.borrow_mut().prev = None;
+        assert_eq!(list.validate(), Err(ListError::BrokenPrev { index: 2 }));
+        third.borrow_mut().prev = saved;
+
+        let mut list = list;
+        list.tail = Some(Rc::downgrade(&second));
+        assert_eq!(
+            list.validate(),
+            Err(ListError::TailMismatch {
+                last_index: Some(2)
+            })
+        );
+        list.tail = Some(Rc::downgrade(&third));
+
+        drop((head, second, third));
+        assert_eq!(list.validate(), Ok(()));
+        list.len = 4;
+        assert_eq!(
+            list.validate(),
+            Err(ListError::LengthMismatch {
+                expected: 4,
+                found: 3
+            })
+        );
+        list.len = 3;
+
+        let other = LinkedList::<i32>::new();
+        let second = list.head.as_ref().unwrap().borrow().next.clone().unwrap();
+        let saved = second.borrow_mut().owner.replace(Rc::clone(&other.owner));
+        assert_eq!(list.validate(), Err(ListError::ForeignNode { index: 1 }));
+        second.borrow_mut().owner = saved;
+        drop(second);
+        assert_eq!(list.validate(), Ok(()));
+
+        let mut empty = LinkedList::<i32>::new();
+        empty.tail = list.tail.clone();
+        assert_eq!(
+            empty.validate(),
+            Err(ListError::TailMismatch { last_index: None })
+        );
+    }
+
+    #[test]
+    fn test_detects_cycles_and_extra_owners() {
+        let list = list_of(&[1, 2, 3]);
+        let head = list.head.clone().unwrap();
+
+        assert_eq!(
+            list.validate(),
+            Err(ListError::UnexpectedStrongCount {
+                index: 0,
+                owners: 2
+            })
+        );
+        assert_eq!(
+            list.validate().unwrap_err().to_string(),
+            "node 0 has 2 strong owners instead of 1"
+        );
+
+        let second = head.borrow().next.clone().unwrap();
+        let third = second.borrow().next.clone().unwrap();
+        third.borrow_mut().next = Some(Rc::clone(&second));
+        assert_eq!(
+            list.validate(),
+            Err(ListError::Cycle {
+                index: 2,
+                target: 1
+            })
+        );
+
+        third.borrow_mut().next = None;
+        drop((head, second, third));
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_while_value_is_borrowed() {
+        let mut list = list_of(&[1, 2]);
+        list.append(&mut list_of(&[3]));
+
+        // The guard is an extra owner, but checking must not panic
+        let front = list.front().unwrap();
+        let value = front.borrow();
+        assert_eq!(
+            list.validate(),
+            Err(ListError::UnexpectedStrongCount {
+                index: 0,
+                owners: 2
+            })
+        );
+        assert_eq!(*value, 1);
+
+        drop(value);
+        drop(front);
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_random_operations_keep_invariants() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        let mut list = LinkedList::new();
+        let mut model = VecDeque::new();
+        let mut handles: Vec<NodeHandle<u32>> = Vec::new();
+
+        for step in 0..3_000_u32 {
+            match rng.below(9) {
+                0 => {
+                    handles.push(list.push_back(step));
+                    model.push_back(step);
+                }
+                1 => {
+                    handles.push(list.push_front(step));
+                    model.push_front(step);
+                }
+                2 => assert_eq!(list.pop_front(), model.pop_front()),
+                3 => assert_eq!(list.pop_back(), model.pop_back()),
+                4 if !handles.is_empty() => {
+                    let handle = handles.swap_remove(rng.below(handles.len()));
+                    if let Ok(value) = list.remove(&handle) {
+                        let at = model.iter().position(|&v| v == value).unwrap();
+                        model.remove(at);
+                    }
+                }
+                5 if !handles.is_empty() => {
+                    let handle = &handles[rng.below(handles.len())];
+                    if let Ok(new_handle) = list.insert_after(handle, step) {
+                        let value = handle.upgrade().unwrap().borrow().value;
+                        let at = model.iter().position(|&v| v == value).unwrap();
+                        model.insert(at + 1, step);
+                        handles.push(new_handle);
+                    }
+                }
+                6 => {
+                    let at = rng.below(model.len() + 1);
+                    let mut cursor = list.cursor_front_mut();
+                    (0..at).for_each(|_| cursor.move_next());
+                    if rng.below(2) == 0 {
+                        cursor.insert_before(step);
+                        model.insert(at, step);
+                    } else if let Some(value) = cursor.remove_current() {
+                        assert_eq!(model.remove(at), Some(value));
+                    }
+                }
+                7 => {
+                    let at = rng.below(model.len() + 1);
+                    let mut rest = list.split_off(at);
+                    assert_eq!(rest.validate(), Ok(()));
+                    assert_eq!(list.validate(), Ok(()));
+                    list.append(&mut rest);
+                }
+                _ if !handles.is_empty() => {
+                    let handle = &handles[rng.below(handles.len())];
+                    if list.move_to_front(handle).is_ok() {
+                        let value = *list.front().unwrap().borrow();
+                        let at = model.iter().position(|&v| v == value).unwrap();
+                        model.remove(at);
+                        model.push_front(value);
+                    }
+                }
+                _ => {}
+            }
+
+            if let Err(error) = list.validate() {
+                panic!("step {}: {}", step, error);
+            }
+            assert_eq!(list.len(), model.len());
+        }
+
+        assert_eq!(list.traverse_forward(), Vec::from(model));
+    }
+}